@@ -1,19 +1,92 @@
-use crate::task::Task;
-use crate::task_manager::{SortOrder, TasksService};
+use crate::command::Command;
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::History;
+use crate::keymap::KeyChord;
+use crate::project::{Project, DEFAULT_PROJECT_ID};
+use crate::query::{Query, Term};
+use crate::search::SearchHit;
+use crate::task::{Task, TaskNode};
+use crate::task_manager::{SortBy, SortOrder, TasksService};
+use crate::text_input::TextInput;
+use crate::view::{View, DEFAULT_VIEW_ID};
 use ratatui::widgets::ListState;
+use std::collections::HashSet;
+
+/// Position of a task inside the tree of subtasks, one for every row in the list
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct TreeRow {
+    pub depth: usize,
+    pub subtasks: usize,
+    pub completed_subtasks: usize,
+    pub collapsed: bool,
+}
 
 pub struct TaskList {
+    /// Visible tasks, subtasks follow their parent unless it is collapsed
     pub items: Vec<Task>,
+    /// Tree position of each task in `items`
+    pub rows: Vec<TreeRow>,
     pub state: ListState,
+    /// Ids of the tasks whose subtasks are hidden
+    pub collapsed: HashSet<i32>,
+    /// Rows of the list visible on screen, set when the list is drawn
+    pub page_height: usize,
+}
+
+impl Default for TaskList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TaskList {
     pub fn new() -> TaskList {
         TaskList {
             items: vec![],
+            rows: vec![],
             state: ListState::default(),
+            collapsed: HashSet::new(),
+            page_height: 0,
+        }
+    }
+
+    /// Replace the content of the list with `tasks`, shown flat without subtasks
+    pub fn set_flat(&mut self, tasks: Vec<Task>) {
+        self.rows = vec![TreeRow::default(); tasks.len()];
+        self.items = tasks;
+    }
+
+    /// Replace the content of the list with the tasks in `forest`, flattened depth first
+    pub fn set_tree(&mut self, forest: Vec<TaskNode>) {
+        self.items.clear();
+        self.rows.clear();
+        for node in forest {
+            self.push_node(node, 0);
+        }
+    }
+
+    fn push_node(&mut self, node: TaskNode, depth: usize) {
+        let (completed_subtasks, subtasks) = node.progress();
+        let collapsed = self.collapsed.contains(&node.task.id);
+        self.items.push(node.task);
+        self.rows.push(TreeRow {
+            depth,
+            subtasks,
+            completed_subtasks,
+            collapsed,
+        });
+        if !collapsed {
+            for child in node.children {
+                self.push_node(child, depth + 1);
+            }
         }
     }
+
+    /// Selected task, if any
+    pub fn selected(&self) -> Option<&Task> {
+        self.state.selected().and_then(|index| self.items.get(index))
+    }
 }
 #[derive(Debug)]
 pub enum InputMode {
@@ -21,67 +94,172 @@ pub enum InputMode {
     Adding,
     EditingExisting,
     Export,
+    Import,
+    Search,
+    Filter,
+    PickingView,
+    AddingView,
+    AddingProject,
+    DeleteTaskWithSubtasks,
+    CommandLine,
+    /// The list shows the deleted tasks, to restore or purge them
+    Trash,
+    /// The list shows the archived tasks, to take them out of the archive
+    Archive,
 }
 #[derive(Debug)]
 pub enum InputFieldType {
     Title,
     Description,
     Date,
+    Tags,
+    Recurrence,
+}
+
+/// A command waiting for the user to confirm it, see `execute_or_confirm`
+pub struct Confirmation {
+    /// Question asked to the user, e.g. `Delete "buy milk"?`
+    pub message: String,
+    pub command: Box<dyn Command>,
 }
 
 pub struct AppContext {
     pub task_list: TaskList,
-    pub input_title: String,
-    pub input_description: String,
-    pub input_date: String,
-    pub input_export_path: String,
+    pub input_title: TextInput,
+    pub input_description: TextInput,
+    pub input_date: TextInput,
+    pub input_tags: TextInput,
+    pub input_recurrence: TextInput,
+    pub input_export_path: TextInput,
+    pub input_import_path: TextInput,
+    pub input_project_name: String,
+    /// Name of the view being saved from the current filter
+    pub input_view_name: String,
+    /// Words searched in titles and descriptions, the list shows only the
+    /// matching tasks while it is not empty
+    pub input_search: String,
+    /// Filter being typed, see `Query` for the syntax
+    pub input_filter: String,
+    /// Command being typed after `:`, see `LineCommand`
+    pub input_command: String,
+    /// Filter applied to the tasks of the active project
+    pub filter: Query,
+    /// Parent of the task being added, when adding a subtask
+    pub input_parent: Option<i32>,
     pub input_mode: InputMode,
     pub input_field: InputFieldType,
     pub tasks_service: TasksService,
     pub show_help: bool,
+    /// Show the details of the selected task next to the list
+    pub show_details: bool,
+    /// Leave the completed tasks out of the list
+    pub hide_completed: bool,
+    /// Command waiting for the user to confirm it, shown in a dialog over every mode
+    pub confirmation: Option<Confirmation>,
     pub error: Option<String>,
-    pub sort_order: SortOrder,
+    /// Outcome of the last command, e.g. a summary of an import
+    pub info: Option<String>,
+    pub projects: Vec<Project>,
+    /// Index in `projects` of the project whose tasks are shown
+    pub active_project: usize,
+    /// Saved views, the active one filters and sorts the tasks of the active project
+    pub views: Vec<View>,
+    /// Index in `views` of the active view
+    pub active_view: usize,
+    /// View highlighted in the view picker
+    pub view_picker: ListState,
+    /// Matches of `input_search`, in the same order as the tasks in `task_list`
+    pub search_hits: Vec<SearchHit>,
+    /// Changes made to the tasks that can be undone
+    pub history: History,
+    pub config: Config,
+    /// Keys of a sequence typed so far in the tasks list, like the first `g` of `gg`
+    pub pending_keys: Vec<KeyChord>,
+    /// Count typed before the keys of an action, like the 5 of `5j`
+    pub pending_count: Option<usize>,
+    /// Set to leave the application
+    pub quit: bool,
 }
 
 impl AppContext {
-    pub fn new(db_path: String) -> AppContext {
-        AppContext {
+    pub fn new(db_path: String) -> Result<AppContext> {
+        Self::with_config(Config {
+            database: db_path.into(),
+            ..Config::default()
+        })
+    }
+
+    /// Open the database of `config`, an empty path opens an in memory database
+    pub fn with_config(config: Config) -> Result<AppContext> {
+        let tasks_service = TasksService::new(config.database.display().to_string())?;
+        tasks_service.purge_expired_trash(config.trash_retention_days)?;
+        tasks_service.archive_completed(config.archive_after_days)?;
+        let projects = tasks_service.get_all_projects()?;
+        let mut views = tasks_service.get_all_views()?;
+        // the default view starts with the configured sort, whatever sort it was left with
+        if let Some(view) = views.iter_mut().find(|view| view.id == DEFAULT_VIEW_ID) {
+            view.sort_by = config.default_sort;
+            view.sort_order = config.default_sort_order;
+        }
+        Ok(AppContext {
             task_list: TaskList::new(),
-            input_title: String::new(),
-            input_description: String::new(),
-            input_date: String::new(),
-            input_export_path: String::new(),
+            input_title: TextInput::new(),
+            input_description: TextInput::multiline(),
+            input_date: TextInput::new(),
+            input_tags: TextInput::new(),
+            input_recurrence: TextInput::new(),
+            input_export_path: TextInput::new(),
+            input_import_path: TextInput::new(),
+            input_project_name: String::new(),
+            input_view_name: String::new(),
+            input_search: String::new(),
+            input_filter: String::new(),
+            input_command: String::new(),
+            filter: Query::all(),
+            input_parent: None,
             input_mode: InputMode::View,
             input_field: InputFieldType::Title,
-            tasks_service: TasksService::new(db_path),
+            tasks_service,
             show_help: false,
+            show_details: true,
+            hide_completed: config.hide_completed,
+            confirmation: None,
             error: None,
-            sort_order: SortOrder::High
-        }
+            info: None,
+            projects,
+            active_project: 0,
+            views,
+            active_view: 0,
+            view_picker: ListState::default(),
+            search_hits: vec![],
+            history: History::new(),
+            config,
+            pending_keys: vec![],
+            pending_count: None,
+            quit: false,
+        })
     }
 
-    pub fn sort_by_priority(&mut self) {
-        self.cycle_sort_order();
-        match self.sort_order {
-            SortOrder::High => self.task_list
-                .items
-                .sort_by(|a, b| b.priority.cmp(&a.priority)),
-            SortOrder::Low => self.task_list
-                .items
-                .sort_by(|a, b| a.priority.cmp(&b.priority))
-        }
+    pub fn sort_by_priority(&mut self) -> Result<()> {
+        self.sort_active_view(SortBy::Priority, None)
+    }
+
+    pub fn sort_by_date(&mut self) -> Result<()> {
+        self.sort_active_view(SortBy::Date, None)
     }
 
-    pub fn sort_by_date(&mut self) {
-        self.cycle_sort_order();
-        match self.sort_order {
-            SortOrder::High => self.task_list
-                .items
-                .sort_by(|a, b| b.date.cmp(&a.date)),
-            SortOrder::Low => self.task_list
-                .items
-                .sort_by(|a, b| a.date.cmp(&b.date))
+    /// Sort the active view by `sort_by` in `sort_order`, switching its sort order
+    /// when None, the view is saved
+    pub fn sort_active_view(&mut self, sort_by: SortBy, sort_order: Option<SortOrder>) -> Result<()> {
+        if let Some(view) = self.views.get_mut(self.active_view) {
+            view.sort_by = sort_by;
+            view.sort_order = sort_order.unwrap_or(match view.sort_order {
+                SortOrder::High => SortOrder::Low,
+                SortOrder::Low => SortOrder::High,
+            });
+            self.tasks_service.update_view(view)?;
         }
+        self.refresh_task_list()
     }
 
     pub fn select_none(&mut self) {
@@ -104,44 +282,184 @@ impl AppContext {
         self.task_list.state.select_last();
     }
 
-    pub fn refresh_task_list(&mut self) {
-        self.task_list.items = self.tasks_service.get_all_tasks_sorted_by_priority(self.sort_order);
+    /// Select the task at `line` of the list, starting from 1, or the last one
+    /// when the list is shorter
+    pub fn select_line(&mut self, line: usize) {
+        if !self.task_list.items.is_empty() {
+            let index = line.saturating_sub(1).min(self.task_list.items.len() - 1);
+            self.task_list.state.select(Some(index));
+        }
+    }
+
+    pub fn select_half_page_down(&mut self) {
+        let rows = self.half_page();
+        self.task_list.state.scroll_down_by(rows);
+    }
+
+    pub fn select_half_page_up(&mut self) {
+        let rows = self.half_page();
+        self.task_list.state.scroll_up_by(rows);
+    }
+
+    fn half_page(&self) -> u16 {
+        (self.task_list.page_height / 2).clamp(1, u16::MAX as usize) as u16
+    }
+
+    /// Load the tasks of the active project matching the active view and the filter,
+    /// or the tasks of every project matching the search when searching, or the
+    /// tasks in the trash when it is open. The selected task stays selected
+    pub fn refresh_task_list(&mut self) -> Result<()> {
+        let selected_id = self.task_list.selected().map(|t| t.id);
+        if let InputMode::Trash = self.input_mode {
+            self.search_hits.clear();
+            self.task_list.set_flat(self.tasks_service.get_trash()?);
+        } else if let InputMode::Archive = self.input_mode {
+            self.search_hits.clear();
+            self.task_list.set_flat(self.tasks_service.get_archive()?);
+        } else if self.input_search.trim().is_empty() {
+            self.search_hits.clear();
+            let (query, sort_by, sort_order) = match self.views.get(self.active_view) {
+                Some(view) => (
+                    Query::And(vec![view.parse_query()?, self.filter.clone()]),
+                    view.sort_by,
+                    view.sort_order,
+                ),
+                None => (self.filter.clone(), self.config.default_sort, self.config.default_sort_order),
+            };
+            let query = match self.hide_completed {
+                true => Query::And(vec![query, Query::Term(Term::Completed(false))]),
+                false => query,
+            };
+            let forest = self.tasks_service.get_task_tree(
+                self.active_project_id(),
+                &query,
+                sort_by,
+                sort_order,
+            )?;
+            self.task_list.set_tree(forest);
+        } else {
+            self.search_hits = self.tasks_service.search(&self.input_search)?;
+            if self.hide_completed {
+                self.search_hits.retain(|hit| !hit.task.completed);
+            }
+            self.task_list
+                .set_flat(self.search_hits.iter().map(|hit| hit.task.clone()).collect());
+        }
+        if let Some(index) = selected_id
+            .and_then(|id| self.task_list.items.iter().position(|t| t.id == id))
+        {
+            self.task_list.state.select(Some(index));
+        }
+        Ok(())
+    }
+
+    /// Hide the subtasks of the selected task
+    pub fn collapse_selected(&mut self) -> Result<()> {
+        if let Some(task) = self.task_list.selected() {
+            let id = task.id;
+            self.task_list.collapsed.insert(id);
+            self.refresh_task_list()?;
+        }
+        Ok(())
+    }
+
+    /// Show the subtasks of the selected task
+    pub fn expand_selected(&mut self) -> Result<()> {
+        if let Some(task) = self.task_list.selected() {
+            let id = task.id;
+            self.task_list.collapsed.remove(&id);
+            self.refresh_task_list()?;
+        }
+        Ok(())
+    }
+
+    /// Reload the projects keeping the active one selected when it still exists
+    pub fn refresh_projects(&mut self) -> Result<()> {
+        let active_id = self.active_project_id();
+        self.projects = self.tasks_service.get_all_projects()?;
+        self.active_project = self
+            .projects
+            .iter()
+            .position(|p| p.id == active_id)
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    pub fn active_project_id(&self) -> i32 {
+        self.projects
+            .get(self.active_project)
+            .map(|p| p.id)
+            .unwrap_or(DEFAULT_PROJECT_ID)
+    }
+
+    /// Make the next project active, wrapping around after the last one
+    pub fn next_project(&mut self) -> Result<()> {
+        if !self.projects.is_empty() {
+            self.active_project = (self.active_project + 1) % self.projects.len();
+        }
+        self.select_none();
+        self.refresh_task_list()
+    }
+
+    /// Make the previous project active, wrapping around before the first one
+    pub fn previous_project(&mut self) -> Result<()> {
+        if !self.projects.is_empty() {
+            self.active_project =
+                (self.active_project + self.projects.len() - 1) % self.projects.len();
+        }
+        self.select_none();
+        self.refresh_task_list()
+    }
+
+    /// Reload the views keeping the active one selected when it still exists
+    pub fn refresh_views(&mut self) -> Result<()> {
+        let active_id = self.views.get(self.active_view).map(|v| v.id);
+        self.views = self.tasks_service.get_all_views()?;
+        self.active_view = self
+            .views
+            .iter()
+            .position(|v| Some(v.id) == active_id)
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    /// Make the view at `index` in `views` active
+    pub fn select_view(&mut self, index: usize) -> Result<()> {
+        if index < self.views.len() {
+            self.active_view = index;
+        }
+        self.select_none();
+        self.refresh_task_list()
     }
 
     pub fn next_input_field(&mut self) {
         self.input_field = match self.input_field {
             InputFieldType::Title => InputFieldType::Description,
             InputFieldType::Description => InputFieldType::Date,
-            InputFieldType::Date => InputFieldType::Title,
-        }
-    }
-
-    pub fn cycle_sort_order(&mut self) {
-        self.sort_order = match self.sort_order {
-            SortOrder::High => SortOrder::Low,
-            SortOrder::Low => SortOrder::High,
+            InputFieldType::Date => InputFieldType::Tags,
+            InputFieldType::Tags => InputFieldType::Recurrence,
+            InputFieldType::Recurrence => InputFieldType::Title,
         }
     }
 
-    pub fn handle_backspace(&mut self) {
+    /// Input of the field being edited when adding or editing a task
+    pub fn focused_input(&self) -> &TextInput {
         match self.input_field {
-            InputFieldType::Title => {
-                self.input_title.pop();
-            }
-            InputFieldType::Description => {
-                self.input_description.pop();
-            }
-            InputFieldType::Date => {
-                self.input_date.pop();
-            }
+            InputFieldType::Title => &self.input_title,
+            InputFieldType::Description => &self.input_description,
+            InputFieldType::Date => &self.input_date,
+            InputFieldType::Tags => &self.input_tags,
+            InputFieldType::Recurrence => &self.input_recurrence,
         }
     }
 
-    pub fn handle_char_input(&mut self, c: char) {
+    pub fn focused_input_mut(&mut self) -> &mut TextInput {
         match self.input_field {
-            InputFieldType::Title => self.input_title.push(c),
-            InputFieldType::Description => self.input_description.push(c),
-            InputFieldType::Date => self.input_date.push(c),
+            InputFieldType::Title => &mut self.input_title,
+            InputFieldType::Description => &mut self.input_description,
+            InputFieldType::Date => &mut self.input_date,
+            InputFieldType::Tags => &mut self.input_tags,
+            InputFieldType::Recurrence => &mut self.input_recurrence,
         }
     }
 }
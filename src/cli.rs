@@ -0,0 +1,435 @@
+use crate::config::Config;
+use crate::date::{default_timezone, format_due, format_timestamp, Due, DATE_FORMAT, TIME_FORMAT};
+use crate::export::{
+    calendar_file_names, export_projects_to_icalendar, export_tasks_to_icalendar, import_tasks_from_icalendar,
+    write_to_file,
+};
+use crate::project::{Project, DEFAULT_PROJECT_ID};
+use crate::query::Query;
+use crate::recurrence::Recurrence;
+use crate::task::{Priority, Task};
+use crate::task_manager::TasksService;
+use crate::view::View;
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "Usage: taskrs [--db path] [--config path] [COMMAND]
+
+Without a command the interactive interface is started.
+
+Options:
+  --db <path>            database to use instead of the one of the config file,
+                         $XDG_DATA_HOME/task-rustler/tasks.db by default
+  --config <path>        config file to read instead of
+                         $XDG_CONFIG_HOME/task-rustler/config.toml
+
+Commands:
+  add <title> [--due \"dd-mm-yyyy [HH:MM] [timezone]\"] [--priority high|medium|low] [--description text]
+      [--tags \"a b\"] [--project name] [--parent id] [--repeat rule]
+                         add a task and print its id
+  list [--project name] [--view name] [--filter query] [--json]
+                         list the tasks in the default sort of the config,
+                         highest priority first unless set, or in the
+                         order of the saved view, --filter takes a query
+                         like \"priority:high status:open due<2026-11-01\"
+  done <id>              mark a task as completed
+  rm <id>                move a task and its subtasks to the trash
+  trash list [--json]    list the tasks in the trash, the last deleted first
+  trash restore <id>     bring a task back from the trash
+  trash empty            permanently delete the tasks in the trash
+  export [--out path] [--project name]
+                         export the tasks in iCalendar format, to stdout when
+                         no path is given, one file per project when the path
+                         is a directory
+  import <path> [--project name]
+                         import the to-dos of an iCalendar file
+  help                   show this message";
+
+/// Options of the `add` subcommand
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct AddArgs {
+    pub title: String,
+    pub due: Option<String>,
+    pub priority: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<String>,
+    pub project: Option<String>,
+    pub parent: Option<i32>,
+    pub repeat: Option<String>,
+}
+
+/// A non-interactive command given on the command line
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CliCommand {
+    Add(AddArgs),
+    List { project: Option<String>, view: Option<String>, filter: Query, json: bool },
+    Done { id: i32 },
+    Remove { id: i32 },
+    TrashList { json: bool },
+    TrashRestore { id: i32 },
+    TrashEmpty,
+    Export { out: Option<String>, project: Option<String> },
+    Import { path: String, project: Option<String> },
+    Help,
+}
+
+/// Options given before the command
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct GlobalOptions {
+    pub db: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+}
+
+/// Split the options given before the command, `--db` and `--config`, from the
+/// command and its arguments
+pub fn parse_global_options(mut args: &[String]) -> Result<(GlobalOptions, &[String])> {
+    let mut options = GlobalOptions::default();
+    while let Some(name) = args.first().and_then(|arg| arg.strip_prefix("--")) {
+        let target = match name {
+            "db" => &mut options.db,
+            "config" => &mut options.config,
+            _ => break,
+        };
+        let value = args.get(1).ok_or_else(|| anyhow!("Missing value for --{name}"))?;
+        *target = Some(PathBuf::from(value));
+        args = &args[2..];
+    }
+    Ok((options, args))
+}
+
+/// Parse the command line arguments, program name excluded.
+/// Returns None when no command is given and the UI should be started
+pub fn parse_args(args: &[String]) -> Result<Option<CliCommand>> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    let mut positional = vec![];
+    let mut options = Options::default();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            positional.push(arg.clone());
+            continue;
+        };
+        if name == "json" {
+            options.json = true;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| anyhow!("Missing value for --{name}"))?
+            .clone();
+        match name {
+            "due" => options.due = Some(value),
+            "priority" => options.priority = Some(value),
+            "description" => options.description = Some(value),
+            "tags" => options.tags = Some(value),
+            "project" => options.project = Some(value),
+            "parent" => options.parent = Some(parse_id(&value)?),
+            "repeat" => options.repeat = Some(value),
+            "out" => options.out = Some(value),
+            "filter" => options.filter = value.parse()?,
+            "view" => options.view = Some(value),
+            _ => bail!("Unknown option --{name}"),
+        }
+    }
+
+    let command = match command.as_str() {
+        "add" => {
+            let title = positional.join(" ");
+            if title.trim().is_empty() {
+                bail!("You must insert at least a title for the task");
+            }
+            CliCommand::Add(AddArgs {
+                title,
+                due: options.due,
+                priority: options.priority,
+                description: options.description,
+                tags: options.tags,
+                project: options.project,
+                parent: options.parent,
+                repeat: options.repeat,
+            })
+        }
+        "list" => CliCommand::List {
+            project: options.project,
+            view: options.view,
+            filter: options.filter,
+            json: options.json,
+        },
+        "done" => CliCommand::Done {
+            id: single_id(&positional)?,
+        },
+        "rm" => CliCommand::Remove {
+            id: single_id(&positional)?,
+        },
+        "trash" => match positional.split_first() {
+            Some((action, [])) if action == "list" => CliCommand::TrashList { json: options.json },
+            Some((action, ids)) if action == "restore" => CliCommand::TrashRestore { id: single_id(ids)? },
+            Some((action, [])) if action == "empty" => CliCommand::TrashEmpty,
+            _ => bail!("Expected trash list, trash restore <id> or trash empty"),
+        },
+        "export" => CliCommand::Export {
+            out: options.out,
+            project: options.project,
+        },
+        "import" => match positional.as_slice() {
+            [path] => CliCommand::Import {
+                path: path.clone(),
+                project: options.project,
+            },
+            _ => bail!("Expected the path of the file to import"),
+        },
+        "help" | "--help" | "-h" => CliCommand::Help,
+        other => bail!("Unknown command {other:?}\n\n{USAGE}"),
+    };
+    Ok(Some(command))
+}
+
+#[derive(Default)]
+struct Options {
+    due: Option<String>,
+    priority: Option<String>,
+    description: Option<String>,
+    tags: Option<String>,
+    project: Option<String>,
+    parent: Option<i32>,
+    repeat: Option<String>,
+    out: Option<String>,
+    view: Option<String>,
+    filter: Query,
+    json: bool,
+}
+
+fn parse_id(value: &str) -> Result<i32> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid task id {value:?}"))
+}
+
+fn single_id(positional: &[String]) -> Result<i32> {
+    match positional {
+        [id] => parse_id(id),
+        _ => bail!("Expected a single task id"),
+    }
+}
+
+/// Parse a priority given by name, first letter or number, e.g. `high`, `h` or `1`
+pub fn parse_priority(value: &str) -> Result<Priority> {
+    Priority::parse(value).ok_or_else(|| anyhow!("Invalid priority {value:?}, use high, medium or low"))
+}
+
+fn find_project(service: &TasksService, name: &str) -> Result<Project> {
+    service
+        .get_all_projects()?
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| anyhow!("No project named {name:?}"))
+}
+
+fn find_view(service: &TasksService, name: &str) -> Result<View> {
+    let views = service.get_all_views()?;
+    match views.iter().find(|v| v.name.eq_ignore_ascii_case(name.trim())) {
+        Some(view) => Ok(view.clone()),
+        None => {
+            let names: Vec<&str> = views.iter().map(|v| v.name.as_str()).collect();
+            bail!("No view named {name:?}, the views are: {}", names.join(", "))
+        }
+    }
+}
+
+/// Execute `command` writing its output to `out`
+pub fn run(command: CliCommand, service: &TasksService, config: &Config, out: &mut impl Write) -> Result<()> {
+    match command {
+        CliCommand::Add(args) => {
+            let id = add_task(service, config, args)?;
+            writeln!(out, "{id}")?;
+        }
+        CliCommand::List { project, view, filter, json } => {
+            let projects = service.get_all_projects()?;
+            let (query, sort_by, sort_order) = match view {
+                Some(name) => {
+                    let view = find_view(service, &name)?;
+                    (Query::And(vec![view.parse_query()?, filter]), view.sort_by, view.sort_order)
+                }
+                None => (filter, config.default_sort, config.default_sort_order),
+            };
+            let query = match project {
+                Some(name) => query.in_project(find_project(service, &name)?.id),
+                None => query,
+            };
+            let tasks = service.find_tasks(&query, sort_by, sort_order)?;
+            if json {
+                let tasks: Vec<Value> = tasks.iter().map(|t| task_to_json(t, &projects)).collect();
+                writeln!(out, "{}", serde_json::to_string_pretty(&tasks)?)?;
+            } else {
+                for task in &tasks {
+                    writeln!(out, "{}", task_to_line(task))?;
+                }
+            }
+        }
+        CliCommand::Done { id } => {
+            let task = service
+                .get_task(id)?
+                .ok_or_else(|| anyhow!("No task with id {id}"))?;
+            if !task.completed {
+                service.toggle_task_status(id, true)?;
+            }
+        }
+        CliCommand::Remove { id } => {
+            if service.delete_task(id)? == 0 {
+                bail!("No task with id {id}");
+            }
+        }
+        CliCommand::TrashList { json } => {
+            let tasks = service.get_trash()?;
+            if json {
+                let projects = service.get_all_projects()?;
+                let tasks: Vec<Value> = tasks.iter().map(|t| task_to_json(t, &projects)).collect();
+                writeln!(out, "{}", serde_json::to_string_pretty(&tasks)?)?;
+            } else {
+                for task in &tasks {
+                    let deleted_at = task.deleted_at.map(format_timestamp).unwrap_or_default();
+                    writeln!(out, "{}  (deleted {deleted_at})", task_to_line(task))?;
+                }
+            }
+        }
+        CliCommand::TrashRestore { id } => {
+            if service.restore_from_trash(id)? == 0 {
+                bail!("No task with id {id} in the trash");
+            }
+        }
+        CliCommand::TrashEmpty => {
+            let purged = service.empty_trash()?;
+            writeln!(out, "Permanently deleted {purged} tasks")?;
+        }
+        CliCommand::Export { out: path, project } => {
+            let projects = match project {
+                Some(name) => vec![find_project(service, &name)?],
+                None => service.get_all_projects()?,
+            };
+            let tasks: Vec<Task> = service
+                .get_all_tasks()?
+                .into_iter()
+                .filter(|t| projects.iter().any(|p| p.id == t.project_id))
+                .collect();
+            match path {
+                Some(path) if Path::new(&path).is_dir() => {
+                    let calendars = export_projects_to_icalendar(&projects, &tasks);
+                    for (file_name, calendar) in calendar_file_names(&projects).into_iter().zip(calendars) {
+                        let file = Path::new(&path).join(file_name);
+                        write_to_file(file.display().to_string().as_str(), calendar.to_string().as_str())?;
+                    }
+                }
+                path => {
+                    let calendar_name = match projects.as_slice() {
+                        [project] => project.name.as_str(),
+                        _ => "task rustler",
+                    };
+                    let calendar = export_tasks_to_icalendar(calendar_name, &tasks).to_string();
+                    match path {
+                        Some(path) => write_to_file(path.as_str(), calendar.as_str())?,
+                        None => write!(out, "{calendar}")?,
+                    }
+                }
+            }
+        }
+        CliCommand::Import { path, project } => {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Error reading file {path}"))?;
+            let mut imported = import_tasks_from_icalendar(&content)?;
+            let project_id = match project {
+                Some(name) => find_project(service, &name)?.id,
+                None => DEFAULT_PROJECT_ID,
+            };
+            for task in imported.tasks.iter_mut() {
+                task.project_id = project_id;
+            }
+            // tasks imported before are updated
+            let mut updated = 0;
+            for task in imported.tasks.iter().filter(|t| !t.uid.is_empty()) {
+                if service.get_task_by_uid(&task.uid)?.is_some() {
+                    updated += 1;
+                }
+            }
+            let ids = service.import_tasks(&imported.tasks)?;
+            writeln!(out, "Imported {} new tasks, {} updated", ids.len().saturating_sub(updated), updated)?;
+            for skipped in &imported.skipped {
+                writeln!(out, "Skipped {skipped}")?;
+            }
+        }
+        CliCommand::Help => writeln!(out, "{USAGE}")?,
+    }
+    Ok(())
+}
+
+fn add_task(service: &TasksService, config: &Config, args: AddArgs) -> Result<i32> {
+    let mut task = Task::new();
+    task.priority = config.default_priority.clone();
+    task.title = args.title;
+    task.description = args.description.unwrap_or_default();
+    if let Some(due) = args.due {
+        task.set_due(Some(due.parse::<Due>()?));
+    }
+    if let Some(priority) = args.priority {
+        task.priority = parse_priority(&priority)?;
+    }
+    task.tags = Task::parse_tags(&args.tags.unwrap_or_default());
+    if let Some(repeat) = args.repeat {
+        task.recurrence = Some(repeat.parse::<Recurrence>()?);
+    }
+    task.project_id = match args.project {
+        Some(name) => find_project(service, &name)?.id,
+        None => DEFAULT_PROJECT_ID,
+    };
+    if let Some(parent_id) = args.parent {
+        let parent = service
+            .get_task(parent_id)?
+            .ok_or_else(|| anyhow!("No task with id {parent_id}"))?;
+        task.parent_id = Some(parent.id);
+        task.project_id = parent.project_id;
+    }
+    Ok(service.add_new_task(&task)?)
+}
+
+fn task_to_line(task: &Task) -> String {
+    let mut line = format!(
+        "{:>4} [{}] {:<6} {:<10} {}",
+        task.id,
+        if task.completed { "x" } else { " " },
+        task.priority.name(),
+        task.local_due()
+            .map(|(date, time)| format_due(date, time))
+            .unwrap_or_default(),
+        task.title
+    );
+    for tag in &task.tags {
+        line.push_str(&format!(" #{tag}"));
+    }
+    line
+}
+
+fn task_to_json(task: &Task, projects: &[Project]) -> Value {
+    json!({
+        "id": task.id,
+        "uid": task.uid,
+        "title": task.title,
+        "description": task.description,
+        "completed": task.completed,
+        "priority": task.priority.name(),
+        "due": task.date.0.map(|d| d.format(DATE_FORMAT).to_string()),
+        "due_time": task.due_time.map(|t| t.format(TIME_FORMAT).to_string()),
+        "timezone": task.due_time.map(|_| task.timezone.unwrap_or_else(default_timezone).name()),
+        "due_at": task.due_at().map(|due_at| due_at.to_rfc3339()),
+        "tags": task.tags,
+        "project": projects.iter().find(|p| p.id == task.project_id).map(|p| p.name.as_str()),
+        "parent_id": task.parent_id,
+        "repeat": task.recurrence.as_ref().map(|r| r.to_string()),
+        "completed_at": task.completed_at.map(|completed_at| completed_at.to_rfc3339()),
+        "archived_at": task.archived_at.map(|archived_at| archived_at.to_rfc3339()),
+        "deleted_at": task.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
+    })
+}
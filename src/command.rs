@@ -1,12 +1,72 @@
 use std::env;
-use crate::app::{AppContext, InputFieldType, InputMode};
-use crate::date::{TaskDate, DATE_FORMAT};
+use std::fs;
+use std::path::Path;
+use crate::app::{AppContext, Confirmation, InputFieldType, InputMode};
+use crate::command_line::LineCommand;
+use crate::date::Due;
+use crate::project::Project;
+use crate::query::Query;
+use crate::recurrence::Recurrence;
 use crate::task::Task;
+use crate::text_input::TextInput;
+use crate::view::View;
 use anyhow::{anyhow, Context, Result};
-use crate::export::{export_tasks_to_icalendar, write_to_file};
+use crate::export::{
+    calendar_file_names, export_projects_to_icalendar, export_tasks_to_icalendar, import_tasks_from_icalendar,
+    write_to_file,
+};
+use crate::history::Change;
 
 pub trait Command {
     fn execute(&self, app: &mut AppContext) -> Result<()>;
+
+    /// Question to ask the user before executing the command, None when it
+    /// doesn't need to be confirmed
+    fn confirmation(&self, _app: &AppContext) -> Option<String> {
+        None
+    }
+}
+
+/// Execute `command`, or when it asks for a confirmation and `confirm` is set
+/// in the config keep it in `app.confirmation` until the user answers
+pub fn execute_or_confirm<T: Command + 'static>(command: T, app: &mut AppContext) -> Result<()> {
+    if app.config.confirm {
+        if let Some(message) = command.confirmation(app) {
+            app.confirmation = Some(Confirmation { message, command: Box::new(command) });
+            return Ok(());
+        }
+    }
+    command.execute(app)
+}
+
+/// Execute the command waiting for a confirmation
+pub struct ConfirmCommand;
+impl Command for ConfirmCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        match app.confirmation.take() {
+            Some(confirmation) => confirmation.command.execute(app),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Drop the command waiting for a confirmation
+pub struct CancelConfirmationCommand;
+impl Command for CancelConfirmationCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.confirmation = None;
+        Ok(())
+    }
+}
+
+/// Parse the repeat rule typed by the user, an empty input means the task doesn't repeat
+fn parse_recurrence(input: &str) -> Result<Option<Recurrence>> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(input.parse::<Recurrence>().context(
+        "Invalid repeat rule, use daily, weekly mon,fri, monthly 15, every 2 weeks or after 3 days",
+    )?))
 }
 
 /// Enter in add command input mode
@@ -26,17 +86,40 @@ pub struct AddTaskCommand;
 impl Command for AddTaskCommand {
     fn execute(&self, app: &mut AppContext) -> Result<()> {
         let mut t = Task::new();
+        t.priority = app.config.default_priority.clone();
         if app.input_title.is_empty() {
             return Err(anyhow!("You must insert at least a title for the task"));
         }
+        t.recurrence = parse_recurrence(app.input_recurrence.value())?;
+        app.input_recurrence.clear();
         if !app.input_date.is_empty() {
-            t.date = TaskDate::try_from(app.input_date.drain(..).collect::<String>())
-                .context("Invalid date format, use dd-mm-yyyy")?;
+            t.set_due(Some(app.input_date.take().parse::<Due>()?));
         }
-        t.title = app.input_title.drain(..).collect();
-        t.description = app.input_description.drain(..).collect();
-        app.tasks_service.add_new_task(&t);
-        app.refresh_task_list();
+        t.title = app.input_title.take();
+        t.description = app.input_description.take();
+        t.tags = Task::parse_tags(&app.input_tags.take());
+        t.project_id = app.active_project_id();
+        t.parent_id = app.input_parent.take();
+        let id = app.tasks_service.add_new_task(&t)?;
+        let added = app.tasks_service.get_tasks(&[id])?;
+        app.history.record(Change::new("add task", vec![], added));
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Enter in add command input mode to add a subtask of the selected task
+pub struct EnterAddSubtaskModeCommand;
+
+impl Command for EnterAddSubtaskModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let parent = app
+            .task_list
+            .selected()
+            .ok_or_else(|| anyhow!("Select the task to add a subtask to"))?;
+        app.input_parent = Some(parent.id);
+        app.input_mode = InputMode::Adding;
+        app.input_field = InputFieldType::Title;
         Ok(())
     }
 }
@@ -48,14 +131,22 @@ pub struct StartEditingExistingTaskCommand;
 impl Command for StartEditingExistingTaskCommand {
     fn execute(&self, app: &mut AppContext) -> Result<()> {
         if let Some(index) = app.task_list.state.selected() {
-            app.input_title = app.task_list.items[index].title.clone();
-            app.input_description = app.task_list.items[index].description.clone();
-            app.input_date = app.task_list.items[index]
-                .date
-                .clone()
-                .0
-                .map(|d| d.format(DATE_FORMAT).to_string())
-                .unwrap_or(String::new());
+            app.input_title.set_value(&app.task_list.items[index].title);
+            app.input_description.set_value(&app.task_list.items[index].description);
+            app.input_date.set_value(
+                &app.task_list.items[index]
+                    .due()
+                    .map(|due| due.to_string())
+                    .unwrap_or_default(),
+            );
+            app.input_tags.set_value(&app.task_list.items[index].tags.join(" "));
+            app.input_recurrence.set_value(
+                &app.task_list.items[index]
+                    .recurrence
+                    .as_ref()
+                    .map(|r| r.to_string())
+                    .unwrap_or_default(),
+            );
             app.input_mode = InputMode::EditingExisting;
             app.input_field = InputFieldType::Title;
         }
@@ -72,16 +163,23 @@ impl Command for FinishEditingExistingTaskCommand {
             if app.input_title.is_empty() {
                 return Err(anyhow!("You must insert at least a title for the task"));
             }
+            app.task_list.items[index].recurrence = parse_recurrence(app.input_recurrence.value())?;
+            app.input_recurrence.clear();
             if !app.input_date.is_empty() {
-                app.task_list.items[index].date =
-                    TaskDate::try_from(app.input_date.drain(..).collect::<String>())
-                        .context("Invalid date format, use dd-mm-yyyy")?;
+                let due = app.input_date.take().parse::<Due>()?;
+                app.task_list.items[index].set_due(Some(due));
             } else {
-                app.task_list.items[index].date = TaskDate(None)
+                app.task_list.items[index].set_due(None)
             }
-            app.task_list.items[index].title = app.input_title.drain(..).collect();
-            app.task_list.items[index].description = app.input_description.drain(..).collect();
-            app.tasks_service.update_task(&app.task_list.items[index])
+            app.task_list.items[index].title = app.input_title.take();
+            app.task_list.items[index].description = app.input_description.take();
+            app.task_list.items[index].tags =
+                Task::parse_tags(&app.input_tags.take());
+            let id = app.task_list.items[index].id;
+            let before = app.tasks_service.get_tasks(&[id])?;
+            app.tasks_service.update_task(&app.task_list.items[index])?;
+            let after = app.tasks_service.get_tasks(&[id])?;
+            app.history.record(Change::new("edit task", before, after));
         }
         Ok(())
     }
@@ -99,9 +197,21 @@ impl Command for ToggleTaskStatusCommand {
                 true => false,
                 false => true,
             };
-            let _ = app
-                .tasks_service
-                .toggle_task_status(item.id, item.completed);
+            let (id, completed) = (item.id, item.completed);
+            let before = app.tasks_service.get_tasks(&[id])?;
+            // completing a recurring task adds its next occurrence
+            let next = match completed {
+                true => app.tasks_service.complete_recurring_task(id)?,
+                false => None,
+            };
+            if next.is_none() {
+                app.tasks_service.toggle_task_status(id, completed)?;
+            }
+            let ids: Vec<i32> = [Some(id), next].into_iter().flatten().collect();
+            let after = app.tasks_service.get_tasks(&ids)?;
+            app.history.record(Change::new("toggle task status", before, after));
+            // the progress of the parent tasks changed
+            app.refresh_task_list()?;
         };
         Ok(())
     }
@@ -115,22 +225,439 @@ impl Command for ToggleItemPriorityCommand {
         if let Some(index) = app.task_list.state.selected() {
             let item = &mut app.task_list.items[index];
             item.priority = item.priority.next();
-            app.tasks_service.change_priority(item.id, &item.priority);
+            let id = item.id;
+            let before = app.tasks_service.get_tasks(&[id])?;
+            app.tasks_service.change_priority(id, &item.priority)?;
+            let after = app.tasks_service.get_tasks(&[id])?;
+            app.history.record(Change::new("change priority", before, after));
         }
         Ok(())
     }
 }
 
+/// Delete the selected task. If it has subtasks ask whether to delete them too
+/// or to move them up one level
 pub struct DeleteTaskCommand;
 
 impl Command for DeleteTaskCommand {
     fn execute(&self, app: &mut AppContext) -> Result<()> {
         if let Some(index) = app.task_list.state.selected() {
-            app.tasks_service.delete_task(app.task_list.items[index].id);
-            app.task_list.items.remove(index);
+            if app.task_list.rows[index].subtasks > 0 {
+                app.input_mode = InputMode::DeleteTaskWithSubtasks;
+                return Ok(());
+            }
+            let id = app.task_list.items[index].id;
+            let before = app.tasks_service.get_tasks(&[id])?;
+            app.tasks_service.delete_task(id)?;
+            let after = trashed(app, &before)?;
+            app.history.record(Change::new("delete task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+
+    /// Tasks with subtasks ask what to do with them instead
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        let index = app.task_list.state.selected()?;
+        if app.task_list.rows.get(index)?.subtasks > 0 {
+            return None;
+        }
+        Some(format!("Delete \"{}\"?", app.task_list.items.get(index)?.title))
+    }
+}
+
+/// The tasks of `before` as they are in the trash once deleted, in the same order
+fn trashed(app: &AppContext, before: &[Task]) -> Result<Vec<Task>> {
+    let trash = app.tasks_service.get_trash()?;
+    Ok(before
+        .iter()
+        .filter_map(|task| trash.iter().find(|t| t.id == task.id).cloned())
+        .collect())
+}
+
+/// Delete the tasks with `ids` and their subtasks as a single change, returns
+/// the number of deleted tasks
+fn delete_tasks_with_subtasks(app: &mut AppContext, ids: &[i32]) -> Result<usize> {
+    let mut before: Vec<Task> = vec![];
+    for id in ids {
+        for task in app.tasks_service.get_task_with_subtasks(*id)? {
+            if !before.iter().any(|t| t.id == task.id) {
+                before.push(task);
+            }
+        }
+    }
+    for id in ids {
+        app.tasks_service.delete_task(*id)?;
+    }
+    let deleted = before.len();
+    let after = trashed(app, &before)?;
+    app.history.record(Change::new("delete tasks", before, after));
+    Ok(deleted)
+}
+
+/// Delete `count` tasks of the list starting from the selected one, with their
+/// subtasks, like the 5 of `5dd`. Asks once for all of them
+pub struct DeleteTasksCommand {
+    pub count: usize,
+}
+
+impl DeleteTasksCommand {
+    fn ids(&self, app: &AppContext) -> Vec<i32> {
+        let start = app.task_list.state.selected().unwrap_or(app.task_list.items.len());
+        app.task_list.items.iter().skip(start).take(self.count).map(|t| t.id).collect()
+    }
+}
+
+impl Command for DeleteTasksCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let ids = self.ids(app);
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let deleted = delete_tasks_with_subtasks(app, &ids)?;
+        app.info = Some(format!("Deleted {deleted} tasks"));
+        app.refresh_task_list()?;
+        Ok(())
+    }
+
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        match self.ids(app).len() {
+            0 => None,
+            1 => Some("Delete the selected task and its subtasks?".to_string()),
+            n => Some(format!("Delete the {n} tasks from the selected one and their subtasks?")),
+        }
+    }
+}
+
+/// Delete every task of the list, with their subtasks
+pub struct DeleteListedTasksCommand;
+
+impl Command for DeleteListedTasksCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let ids: Vec<i32> = app.task_list.items.iter().map(|t| t.id).collect();
+        let deleted = delete_tasks_with_subtasks(app, &ids)?;
+        app.info = Some(format!("Deleted {deleted} tasks"));
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        match app.task_list.items.len() {
+            0 => None,
+            1 => Some("Delete the task of the list and its subtasks?".to_string()),
+            n => Some(format!("Delete the {n} tasks of the list and their subtasks?")),
+        }
+    }
+}
+
+/// Move the tasks of every project to the trash
+pub struct ClearTasksCommand;
+
+impl Command for ClearTasksCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let before = app.tasks_service.get_all_tasks()?;
+        let deleted = app.tasks_service.delete_all_tasks()?;
+        app.info = Some(format!("Moved {deleted} tasks to the trash"));
+        let after = trashed(app, &before)?;
+        app.history.record(Change::new("delete every task", before, after));
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+
+    fn confirmation(&self, _app: &AppContext) -> Option<String> {
+        Some("Move every task of every project to the trash?".to_string())
+    }
+}
+
+/// Show the deleted tasks instead of the tasks of the active project
+pub struct EnterTrashCommand;
+impl Command for EnterTrashCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::Trash;
+        app.error = None;
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+pub struct ExitTrashCommand;
+impl Command for ExitTrashCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.error = None;
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Bring the task selected in the trash back to its project
+pub struct RestoreTaskCommand;
+impl Command for RestoreTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(task) = app.task_list.selected() {
+            let id = task.id;
+            let trash = app.tasks_service.get_trash()?;
+            app.tasks_service.restore_from_trash(id)?;
+            let after = app.tasks_service.get_task_with_subtasks(id)?;
+            let before = after
+                .iter()
+                .filter_map(|restored| trash.iter().find(|t| t.id == restored.id).cloned())
+                .collect();
+            app.info = Some(format!("Restored {} tasks", after.len()));
+            app.history.record(Change::new("restore task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+}
+
+/// Permanently delete the task selected in the trash
+pub struct PurgeTaskCommand;
+impl Command for PurgeTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(task) = app.task_list.selected() {
+            app.tasks_service.purge_task(task.id)?;
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        let task = app.task_list.selected()?;
+        Some(format!("Permanently delete \"{}\"? It can't be undone", task.title))
+    }
+}
+
+/// Permanently delete every task in the trash
+pub struct EmptyTrashCommand;
+impl Command for EmptyTrashCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let purged = app.tasks_service.empty_trash()?;
+        app.info = Some(format!("Permanently deleted {purged} tasks"));
+        app.refresh_task_list()?;
+        Ok(())
+    }
+
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        match app.task_list.items.len() {
+            0 => None,
+            n => Some(format!("Permanently delete the {n} tasks in the trash? It can't be undone")),
+        }
+    }
+}
+
+/// Move the selected completed task to the archive with its subtasks, which
+/// must be completed too
+pub struct ArchiveTaskCommand;
+impl Command for ArchiveTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(task) = app.task_list.selected() {
+            if !task.completed {
+                return Err(anyhow!("Only completed tasks can be archived"));
+            }
+            let id = task.id;
+            if app.tasks_service.has_open_subtasks(id)? {
+                return Err(anyhow!("Complete the subtasks before archiving the task"));
+            }
+            let before = app.tasks_service.get_task_with_subtasks(id)?;
+            app.tasks_service.archive_task(id)?;
+            let after = app.tasks_service.get_task_with_subtasks(id)?;
+            app.info = Some(format!("Archived {} tasks", after.len()));
+            app.history.record(Change::new("archive task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+}
+
+/// Show the archived tasks instead of the tasks of the active project
+pub struct EnterArchiveCommand;
+impl Command for EnterArchiveCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::Archive;
+        app.error = None;
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+pub struct ExitArchiveCommand;
+impl Command for ExitArchiveCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.error = None;
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Take the task selected in the archive back to the list of its project
+pub struct UnarchiveTaskCommand;
+impl Command for UnarchiveTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(task) = app.task_list.selected() {
+            let id = task.id;
+            let before = app.tasks_service.get_task_with_subtasks(id)?;
+            app.tasks_service.unarchive_task(id)?;
+            let after = app.tasks_service.get_task_with_subtasks(id)?;
+            let unarchived = before.iter().zip(&after).filter(|(b, a)| b.archived_at != a.archived_at).count();
+            app.info = Some(format!("Took {unarchived} tasks out of the archive"));
+            app.history.record(Change::new("unarchive task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+}
+
+/// Delete the task selected in the archive together with its subtasks, the
+/// archive shows them flat so the confirmation tells how many there are
+pub struct DeleteArchivedTaskCommand;
+impl Command for DeleteArchivedTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(task) = app.task_list.selected() {
+            let id = task.id;
+            let before = app.tasks_service.get_task_with_subtasks(id)?;
+            app.tasks_service.delete_task(id)?;
+            let after = trashed(app, &before)?;
+            app.history.record(Change::new("delete task", before, after));
+            app.refresh_task_list()?;
         }
         Ok(())
     }
+
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        let task = app.task_list.selected()?;
+        let subtasks = app
+            .tasks_service
+            .get_task_with_subtasks(task.id)
+            .map_or(0, |tasks| tasks.len().saturating_sub(1));
+        Some(match subtasks {
+            0 => format!("Delete \"{}\"?", task.title),
+            n => format!("Delete \"{}\" and its {n} subtasks?", task.title),
+        })
+    }
+}
+
+/// Hide the completed tasks from the list, or show them again
+pub struct ToggleCompletedCommand;
+impl Command for ToggleCompletedCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.hide_completed = !app.hide_completed;
+        app.info = Some(match app.hide_completed {
+            true => "Completed tasks hidden".to_string(),
+            false => "Completed tasks shown".to_string(),
+        });
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Delete the selected task together with all its subtasks
+pub struct DeleteTaskAndSubtasksCommand;
+
+impl Command for DeleteTaskAndSubtasksCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        if let Some(task) = app.task_list.selected() {
+            let id = task.id;
+            let before = app.tasks_service.get_task_with_subtasks(id)?;
+            app.tasks_service.delete_task(id)?;
+            let after = trashed(app, &before)?;
+            app.history.record(Change::new("delete task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+}
+
+/// Delete the selected task moving its subtasks under its parent
+pub struct DeleteTaskKeepingSubtasksCommand;
+
+impl Command for DeleteTaskKeepingSubtasksCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        if let Some(task) = app.task_list.selected() {
+            let id = task.id;
+            let subtasks = app.tasks_service.get_subtasks(id)?;
+            let subtask_ids: Vec<i32> = subtasks.iter().map(|t| t.id).collect();
+            let mut before = app.tasks_service.get_tasks(&[id])?;
+            before.extend(subtasks);
+            app.tasks_service.delete_task_keeping_subtasks(id)?;
+            let mut after = trashed(app, &before[..1])?;
+            after.extend(app.tasks_service.get_tasks(&subtask_ids)?);
+            app.history.record(Change::new("delete task", before, after));
+            app.refresh_task_list()?;
+        }
+        Ok(())
+    }
+}
+
+/// Undo the last change made to the tasks
+pub struct UndoCommand;
+
+impl Command for UndoCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if app.history.undo(&app.tasks_service)?.is_none() {
+            return Err(anyhow!("Nothing to undo"));
+        }
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Redo the last undone change
+pub struct RedoCommand;
+
+impl Command for RedoCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if app.history.redo(&app.tasks_service)?.is_none() {
+            return Err(anyhow!("Nothing to redo"));
+        }
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+/// Hide the subtasks of the selected task
+pub struct CollapseTaskCommand;
+
+impl Command for CollapseTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.collapse_selected()?)
+    }
+}
+
+/// Show the subtasks of the selected task
+pub struct ExpandTaskCommand;
+
+impl Command for ExpandTaskCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.expand_selected()?)
+    }
+}
+
+/// Sort by priority, switching between highest and lowest first
+pub struct SortByPriorityCommand;
+
+impl Command for SortByPriorityCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.sort_by_priority()?)
+    }
+}
+
+/// Sort by date, switching between newest and oldest first
+pub struct SortByDateCommand;
+
+impl Command for SortByDateCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.sort_by_date()?)
+    }
 }
 
 /// Stop adding or editing the current task, clear the input fields and
@@ -143,6 +670,9 @@ impl Command for StopEditingCommand {
         app.input_title.clear();
         app.input_description.clear();
         app.input_date.clear();
+        app.input_tags.clear();
+        app.input_recurrence.clear();
+        app.input_parent = None;
         app.error = None;
         Ok(())
     }
@@ -153,18 +683,65 @@ impl Command for EnterExportModeCommand {
     fn execute(&self, app: &mut AppContext) -> Result<()> {
         app.input_mode = InputMode::Export;
         app.error = None;
-        let mut current_dir = env::current_dir().context("Could not access to the current directory")?;
-        current_dir.set_file_name("task_rustler.ics");
-        app.input_export_path = current_dir.display().to_string();
+        app.input_export_path = TextInput::from(default_export_path(app)?);
         Ok(())
     }
 }
 
+/// Path the export starts from, the one of the config or `task_rustler.ics`
+/// next to the current directory
+fn default_export_path(app: &AppContext) -> Result<String> {
+    Ok(match &app.config.export_path {
+        Some(path) => path.display().to_string(),
+        None => {
+            let mut current_dir = env::current_dir().context("Could not access to the current directory")?;
+            current_dir.set_file_name("task_rustler.ics");
+            current_dir.display().to_string()
+        }
+    })
+}
+
+/// Export the tasks of the active project to the file at the input path.
+/// If the path is a directory every project is exported to its own
+/// `<project name>.ics` file inside it, see `calendar_file_names`
 pub struct FinishingExportCommand;
 impl Command for FinishingExportCommand {
     fn execute(&self, app: &mut AppContext) -> Result<()> {
-        let calendar = export_tasks_to_icalendar("task rustler", &app.task_list.items);
-        write_to_file(app.input_export_path.as_str(), calendar.to_string().as_str())
+        let path = Path::new(app.input_export_path.value());
+        if path.is_dir() {
+            let tasks = app.tasks_service.get_all_tasks()?;
+            let calendars = export_projects_to_icalendar(&app.projects, &tasks);
+            for (file_name, calendar) in calendar_file_names(&app.projects).into_iter().zip(calendars) {
+                let file = path.join(file_name);
+                write_to_file(file.display().to_string().as_str(), calendar.to_string().as_str())?;
+            }
+            app.input_mode = InputMode::View;
+            app.info = Some(format!("Exported to {}", path.display()));
+            return Ok(());
+        }
+        let calendar_name = app
+            .projects
+            .get(app.active_project)
+            .map(|p| p.name.as_str())
+            .unwrap_or("task rustler");
+        let calendar = export_tasks_to_icalendar(calendar_name, &app.task_list.items);
+        write_to_file(app.input_export_path.value(), calendar.to_string().as_str())?;
+        app.input_mode = InputMode::View;
+        app.info = Some(format!("Exported to {}", app.input_export_path.value()));
+        Ok(())
+    }
+
+    /// Files the export would replace
+    fn confirmation(&self, app: &AppContext) -> Option<String> {
+        let path = Path::new(app.input_export_path.value());
+        if path.is_dir() {
+            let existing = calendar_file_names(&app.projects)
+                .iter()
+                .filter(|file_name| path.join(file_name).exists())
+                .count();
+            return (existing > 0).then(|| format!("Overwrite {existing} calendars in {}?", path.display()));
+        }
+        path.exists().then(|| format!("Overwrite {}?", path.display()))
     }
 }
 
@@ -177,3 +754,376 @@ impl Command for ExitExportModeCommand {
         Ok(())
     }
 }
+
+pub struct EnterImportModeCommand;
+impl Command for EnterImportModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::Import;
+        app.error = None;
+        app.input_import_path.clear();
+        Ok(())
+    }
+}
+
+/// Import the to-dos of the iCalendar file at the input path into the active project
+pub struct FinishingImportCommand;
+impl Command for FinishingImportCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let path = app.input_import_path.value().trim();
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Error reading file {}: {}", path, e))?;
+        let mut imported = import_tasks_from_icalendar(&content)?;
+        for task in imported.tasks.iter_mut() {
+            task.project_id = app.active_project_id();
+        }
+        // tasks imported before are updated, keep their previous version for undo
+        let mut before = vec![];
+        for task in imported.tasks.iter().filter(|t| !t.uid.is_empty()) {
+            before.extend(app.tasks_service.get_task_by_uid(&task.uid)?);
+        }
+        let ids = app.tasks_service.import_tasks(&imported.tasks)?;
+        let after = app.tasks_service.get_tasks(&ids)?;
+        let updated = before.len();
+        app.history.record(Change::new("import tasks", before, after));
+        app.refresh_task_list()?;
+        let mut info = format!("Imported {} new tasks, {} updated", ids.len().saturating_sub(updated), updated);
+        if !imported.skipped.is_empty() {
+            info.push_str(&format!(
+                ", skipped {}: {}",
+                imported.skipped.len(),
+                imported.skipped.join(", ")
+            ));
+        }
+        app.info = Some(info);
+        app.input_import_path.clear();
+        Ok(())
+    }
+}
+
+pub struct ExitImportModeCommand;
+impl Command for ExitImportModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_import_path.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Enter search mode, the list shows only the tasks matching the search while typing
+pub struct EnterSearchModeCommand;
+impl Command for EnterSearchModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::Search;
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Run the search again after the search input changed, selecting the best match
+pub struct UpdateSearchCommand;
+impl Command for UpdateSearchCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.select_none();
+        app.refresh_task_list()?;
+        if !app.task_list.items.is_empty() {
+            app.select_first();
+        }
+        Ok(())
+    }
+}
+
+/// Leave search mode keeping the list filtered by the search
+pub struct FinishSearchCommand;
+impl Command for FinishSearchCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        Ok(())
+    }
+}
+
+/// Clear the search and show the tasks of the active project again
+pub struct ExitSearchCommand;
+impl Command for ExitSearchCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_search.clear();
+        app.error = None;
+        Ok(app.refresh_task_list()?)
+    }
+}
+
+/// Enter filter mode to type a filter for the tasks of the active project
+pub struct EnterFilterModeCommand;
+impl Command for EnterFilterModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::Filter;
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Apply the typed filter, an empty filter shows every task again
+pub struct FinishFilterCommand;
+impl Command for FinishFilterCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.filter = app.input_filter.parse::<Query>()?;
+        app.select_none();
+        app.refresh_task_list()?;
+        if !app.task_list.items.is_empty() {
+            app.select_first();
+        }
+        app.input_mode = InputMode::View;
+        Ok(())
+    }
+}
+
+/// Clear the filter and show every task of the active project again
+pub struct ExitFilterCommand;
+impl Command for ExitFilterCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_filter.clear();
+        app.filter = Query::all();
+        app.error = None;
+        Ok(app.refresh_task_list()?)
+    }
+}
+
+/// Switch to the next project
+pub struct NextProjectCommand;
+impl Command for NextProjectCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.next_project()?)
+    }
+}
+
+/// Switch to the previous project
+pub struct PreviousProjectCommand;
+impl Command for PreviousProjectCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        Ok(app.previous_project()?)
+    }
+}
+
+pub struct EnterAddProjectModeCommand;
+impl Command for EnterAddProjectModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::AddingProject;
+        app.input_project_name.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Create a new project with the input name and make it the active one
+pub struct AddProjectCommand;
+impl Command for AddProjectCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let project = Project::new(app.input_project_name.as_str());
+        if project.name.is_empty() {
+            return Err(anyhow!("You must insert a name for the project"));
+        }
+        if app.projects.iter().any(|p| p.name == project.name) {
+            return Err(anyhow!("A project named {} already exists", project.name));
+        }
+        let project_id = app.tasks_service.add_new_project(&project)?;
+        app.refresh_projects()?;
+        app.active_project = app
+            .projects
+            .iter()
+            .position(|p| p.id == project_id)
+            .unwrap_or(0);
+        app.input_project_name.clear();
+        app.select_none();
+        app.refresh_task_list()?;
+        Ok(())
+    }
+}
+
+pub struct ExitAddProjectModeCommand;
+impl Command for ExitAddProjectModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_project_name.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Open the view picker with the active view highlighted
+pub struct EnterViewPickerCommand;
+impl Command for EnterViewPickerCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::PickingView;
+        app.view_picker.select(Some(app.active_view));
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Make the view highlighted in the picker the active one
+pub struct PickViewCommand;
+impl Command for PickViewCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        if let Some(index) = app.view_picker.selected() {
+            app.select_view(index)?;
+        }
+        app.input_mode = InputMode::View;
+        Ok(())
+    }
+}
+
+/// Delete the view highlighted in the picker, the default view can't be deleted
+pub struct DeleteViewCommand;
+impl Command for DeleteViewCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let Some(view) = app.view_picker.selected().and_then(|index| app.views.get(index)) else {
+            return Ok(());
+        };
+        app.tasks_service.delete_view(view.id)?;
+        app.refresh_views()?;
+        app.view_picker.select(Some(app.active_view));
+        Ok(app.refresh_task_list()?)
+    }
+}
+
+pub struct ExitViewPickerCommand;
+impl Command for ExitViewPickerCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.error = None;
+        Ok(())
+    }
+}
+
+pub struct EnterAddViewModeCommand;
+impl Command for EnterAddViewModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::AddingView;
+        app.input_view_name.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Save the current filter, together with the active view's filter and sort order,
+/// as a new view with the input name and make it the active one
+pub struct AddViewCommand;
+impl Command for AddViewCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        let Some(active) = app.views.get(app.active_view) else {
+            return Err(anyhow!("No active view"));
+        };
+        let query = match (active.query.trim(), app.input_filter.trim()) {
+            (view, "") => view.to_string(),
+            ("", filter) => filter.to_string(),
+            (view, filter) => format!("({view}) ({filter})"),
+        };
+        let view = View::new(app.input_view_name.as_str(), &query, active.sort_by, active.sort_order);
+        if view.name.is_empty() {
+            return Err(anyhow!("You must insert a name for the view"));
+        }
+        if app.views.iter().any(|v| v.name.eq_ignore_ascii_case(&view.name)) {
+            return Err(anyhow!("A view named {} already exists", view.name));
+        }
+        let view_id = app.tasks_service.add_new_view(&view)?;
+        app.refresh_views()?;
+        // the filter is part of the new view now
+        app.input_filter.clear();
+        app.filter = Query::all();
+        app.input_view_name.clear();
+        let index = app.views.iter().position(|v| v.id == view_id).unwrap_or(0);
+        app.select_view(index)?;
+        Ok(())
+    }
+}
+
+pub struct ExitAddViewModeCommand;
+impl Command for ExitAddViewModeCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_view_name.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Open the command line to type a command like `:sort date`
+pub struct EnterCommandLineCommand;
+impl Command for EnterCommandLineCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::CommandLine;
+        app.input_command.clear();
+        app.error = None;
+        Ok(())
+    }
+}
+
+/// Run the command typed in the command line, see `LineCommand`
+pub struct RunCommandLineCommand;
+impl Command for RunCommandLineCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        let input = std::mem::take(&mut app.input_command);
+        if input.trim().is_empty() {
+            return Ok(());
+        }
+        match input.parse::<LineCommand>()? {
+            LineCommand::Sort(sort_by, sort_order) => app.sort_active_view(sort_by, sort_order)?,
+            LineCommand::Export(path) => {
+                app.input_export_path = TextInput::from(match path {
+                    Some(path) => path,
+                    None => default_export_path(app)?,
+                });
+                execute_or_confirm(FinishingExportCommand, app)?;
+            }
+            LineCommand::Import(path) => {
+                app.input_import_path = TextInput::from(path);
+                FinishingImportCommand.execute(app)?;
+            }
+            LineCommand::Filter(query) => {
+                app.input_filter = query;
+                FinishFilterCommand.execute(app)?;
+            }
+            LineCommand::View(name) => {
+                let index = app
+                    .views
+                    .iter()
+                    .position(|v| v.name.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| anyhow!("No view named {name}"))?;
+                app.select_view(index)?;
+            }
+            LineCommand::Project(name) => {
+                app.active_project = app
+                    .projects
+                    .iter()
+                    .position(|p| p.name.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| anyhow!("No project named {name}"))?;
+                app.select_none();
+                app.refresh_task_list()?;
+            }
+            LineCommand::Goto(line) => app.select_line(line),
+            LineCommand::Delete => execute_or_confirm(DeleteListedTasksCommand, app)?,
+            LineCommand::Clear => execute_or_confirm(ClearTasksCommand, app)?,
+            LineCommand::Trash => EnterTrashCommand.execute(app)?,
+            LineCommand::Archive => EnterArchiveCommand.execute(app)?,
+            LineCommand::Undo => UndoCommand.execute(app)?,
+            LineCommand::Redo => RedoCommand.execute(app)?,
+            LineCommand::Help => app.show_help = true,
+            LineCommand::Write => app.info = Some("Tasks are saved as soon as they change".to_string()),
+            LineCommand::Quit => app.quit = true,
+        }
+        Ok(())
+    }
+}
+
+pub struct ExitCommandLineCommand;
+impl Command for ExitCommandLineCommand {
+    fn execute(&self, app: &mut AppContext) -> Result<()> {
+        app.input_mode = InputMode::View;
+        app.input_command.clear();
+        app.error = None;
+        Ok(())
+    }
+}
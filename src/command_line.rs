@@ -0,0 +1,113 @@
+use crate::task_manager::{SortBy, SortOrder};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A command typed after `:` in the tasks list, like vim's command line
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LineCommand {
+    /// `:sort date low`, without an order the order of the view is switched
+    Sort(SortBy, Option<SortOrder>),
+    /// `:export path` or `:w path`, the default export path when None
+    Export(Option<String>),
+    /// `:import path`
+    Import(String),
+    /// `:filter query`, an empty query clears the filter
+    Filter(String),
+    /// `:view name`
+    View(String),
+    /// `:project name`
+    Project(String),
+    /// `:12` selects the 12th task of the list
+    Goto(usize),
+    /// `:delete`, deletes every task of the list
+    Delete,
+    /// `:clear`, moves the tasks of every project to the trash
+    Clear,
+    /// `:trash`, shows the deleted tasks
+    Trash,
+    /// `:archive`, shows the archived tasks
+    Archive,
+    Undo,
+    Redo,
+    Help,
+    /// `:w`, tasks are saved as soon as they change so there is nothing to write
+    Write,
+    /// `:q`, `:wq` or `:x`
+    Quit,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseCommandError(pub String);
+
+impl Display for ParseCommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid command: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+impl FromStr for LineCommand {
+    type Err = ParseCommandError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let input = input.strip_prefix(':').unwrap_or(input).trim_start();
+        let (name, argument) = match input.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (input, ""),
+        };
+        let no_argument = |command: LineCommand| match argument {
+            "" => Ok(command),
+            _ => Err(ParseCommandError(format!(":{name} takes no argument"))),
+        };
+        let required = |what: &str| match argument {
+            "" => Err(ParseCommandError(format!(":{name} needs {what}"))),
+            argument => Ok(argument.to_string()),
+        };
+        if let Ok(line) = name.parse::<usize>() {
+            return no_argument(LineCommand::Goto(line));
+        }
+        match name {
+            "sort" => parse_sort(argument),
+            "export" => Ok(LineCommand::Export(Some(argument.to_string()).filter(|path| !path.is_empty()))),
+            "w" | "write" if argument.is_empty() => Ok(LineCommand::Write),
+            "w" | "write" => Ok(LineCommand::Export(Some(argument.to_string()))),
+            "import" => Ok(LineCommand::Import(required("a file path")?)),
+            "filter" => Ok(LineCommand::Filter(argument.to_string())),
+            "view" => Ok(LineCommand::View(required("the name of a view")?)),
+            "project" => Ok(LineCommand::Project(required("the name of a project")?)),
+            "delete" => no_argument(LineCommand::Delete),
+            "clear" => no_argument(LineCommand::Clear),
+            "trash" => no_argument(LineCommand::Trash),
+            "archive" => no_argument(LineCommand::Archive),
+            "u" | "undo" => no_argument(LineCommand::Undo),
+            "redo" => no_argument(LineCommand::Redo),
+            "h" | "help" => no_argument(LineCommand::Help),
+            "q" | "q!" | "quit" | "wq" | "x" => no_argument(LineCommand::Quit),
+            "" => Err(ParseCommandError("empty command".to_string())),
+            name => Err(ParseCommandError(format!("unknown command :{name}"))),
+        }
+    }
+}
+
+/// Parse `priority` or `date`, optionally followed by `high` or `low`
+fn parse_sort(argument: &str) -> Result<LineCommand, ParseCommandError> {
+    let err = || ParseCommandError("use :sort priority or :sort date, optionally followed by high or low".to_string());
+    let mut words = argument.split_whitespace();
+    let sort_by = match words.next().map(str::to_lowercase).as_deref() {
+        Some("priority") => SortBy::Priority,
+        Some("date" | "due") => SortBy::Date,
+        _ => return Err(err()),
+    };
+    let sort_order = match words.next().map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("high") => Some(SortOrder::High),
+        Some("low") => Some(SortOrder::Low),
+        Some(_) => return Err(err()),
+    };
+    match words.next() {
+        None => Ok(LineCommand::Sort(sort_by, sort_order)),
+        Some(_) => Err(err()),
+    }
+}
@@ -0,0 +1,171 @@
+use crate::date::{is_valid_date_format, set_default_timezone, set_display_date_format};
+use crate::keymap::{Action, KeyPreset, Keymap, Keys};
+use crate::task::Priority;
+use crate::task_manager::{SortBy, SortOrder};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Name of the directories holding the config file and the database
+const APP_DIR: &str = "task-rustler";
+
+/// Colors of the interface
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Settings read from `config.toml`, every key is optional:
+/// ```toml
+/// database = "~/Documents/tasks.db"
+/// date_format = "%Y-%m-%d"
+/// timezone = "Europe/Rome"
+/// default_priority = "medium"
+/// default_sort = "date"
+/// default_sort_order = "low"
+/// export_path = "~/calendars"
+/// theme = "light"
+/// confirm = false
+/// trash_retention_days = 7
+/// archive_after_days = 14
+/// hide_completed = true
+/// key_preset = "vim"
+///
+/// [keys]
+/// add_task = "n"
+/// select_next = ["down", "j"]
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Path of the database, `$XDG_DATA_HOME/task-rustler/tasks.db` by default
+    pub database: PathBuf,
+    /// strftime format dates are displayed in
+    pub date_format: Option<String>,
+    /// IANA name of the timezone of times without an explicit one
+    pub timezone: Option<String>,
+    /// Priority of new tasks
+    pub default_priority: Priority,
+    /// Sort of the "All tasks" view when the interface starts, and of `taskrs list` without a view
+    pub default_sort: SortBy,
+    pub default_sort_order: SortOrder,
+    /// File or directory the export starts from, the current directory by default
+    pub export_path: Option<PathBuf>,
+    pub theme: Theme,
+    /// Ask before deleting tasks, overwriting a file on export or clearing the database
+    pub confirm: bool,
+    /// Days deleted tasks stay in the trash before being purged, 0 keeps them forever
+    pub trash_retention_days: u32,
+    /// Days after which completed tasks are archived, 0 only archives them by hand
+    pub archive_after_days: u32,
+    /// Start with the completed tasks hidden from the list
+    pub hide_completed: bool,
+    /// Default keys of the tasks list
+    pub key_preset: KeyPreset,
+    /// Keys of the actions of the tasks list, replacing their default keys
+    pub keys: BTreeMap<Action, Keys>,
+    /// Key bindings of `key_preset` with `keys` applied
+    #[serde(skip)]
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database: default_db_path(),
+            date_format: None,
+            timezone: None,
+            default_priority: Priority::Low,
+            default_sort: SortBy::Priority,
+            default_sort_order: SortOrder::High,
+            export_path: None,
+            theme: Theme::default(),
+            confirm: true,
+            trash_retention_days: 30,
+            archive_after_days: 0,
+            hide_completed: false,
+            key_preset: KeyPreset::default(),
+            keys: BTreeMap::new(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file at `path`, or at `default_config_path()` when None.
+    /// A missing default config file gives the default settings, a missing file
+    /// given explicitly is an error
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let config_path = path.map(Path::to_path_buf).unwrap_or_else(default_config_path);
+        match fs::read_to_string(&config_path) {
+            Ok(text) => Config::parse(&text)
+                .with_context(|| format!("Invalid config file {}", config_path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound && path.is_none() => Ok(Config::default()),
+            Err(e) => Err(anyhow!(e)).with_context(|| format!("Error reading config file {}", config_path.display())),
+        }
+    }
+
+    /// Parse the content of a config file, paths starting with `~/` are relative
+    /// to the home directory
+    pub fn parse(text: &str) -> Result<Config> {
+        let mut config: Config = toml::from_str(text)?;
+        if let Some(format) = &config.date_format {
+            if !is_valid_date_format(format) {
+                bail!("Invalid date_format {format:?}, use a strftime format like %d-%m-%Y");
+            }
+        }
+        if let Some(name) = &config.timezone {
+            name.parse::<Tz>()
+                .map_err(|_| anyhow!("Unknown timezone {name:?}, use a name like Europe/Rome"))?;
+        }
+        config.keymap = Keymap::new(config.key_preset, &config.keys)?;
+        config.database = expand_home(&config.database);
+        config.export_path = config.export_path.as_deref().map(expand_home);
+        Ok(config)
+    }
+
+    /// Use the date format and the timezone of the config, it must be called
+    /// before any date is displayed
+    pub fn apply(&self) {
+        if let Some(format) = &self.date_format {
+            set_display_date_format(format);
+        }
+        if let Some(timezone) = self.timezone.as_deref().and_then(|name| name.parse().ok()) {
+            set_default_timezone(timezone);
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/task-rustler/config.toml`
+pub fn default_config_path() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join(APP_DIR).join("config.toml")
+}
+
+/// `$XDG_DATA_HOME/task-rustler/tasks.db`
+pub fn default_db_path() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join(APP_DIR).join("tasks.db")
+}
+
+/// Directory named by the environment variable `var`, or `fallback` inside the
+/// home directory when it is not set. The specification ignores relative paths
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .unwrap_or_default()
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
@@ -1,7 +1,71 @@
-use anyhow::Result;
-use chrono::NaiveDate;
+use crate::date_parser::parse_date;
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+/// Format dates are typed in, and displayed in unless configured otherwise
 pub const DATE_FORMAT: &str = "%d-%m-%Y";
+pub const TIME_FORMAT: &str = "%H:%M";
+/// Format dates are stored in, it sorts like the dates
+pub const ISO_DATE_FORMAT: &str = "%Y-%m-%d";
+
+static DISPLAY_DATE_FORMAT: OnceLock<String> = OnceLock::new();
+
+/// Format dates are displayed in: the one set with `set_display_date_format`,
+/// `DATE_FORMAT` otherwise
+pub fn display_date_format() -> &'static str {
+    DISPLAY_DATE_FORMAT.get_or_init(|| DATE_FORMAT.to_string())
+}
+
+/// Format dates can be typed in besides `DATE_FORMAT` and the ISO one: the display
+/// format when a date written in it can be read back, e.g. not `%a %d %b` that has
+/// no year, otherwise `DATE_FORMAT`
+pub fn input_date_format() -> &'static str {
+    let format = display_date_format();
+    let date = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap_or_default();
+    match NaiveDate::parse_from_str(&date.format(format).to_string(), format) {
+        Ok(parsed) if parsed == date => format,
+        _ => DATE_FORMAT,
+    }
+}
+
+/// `format` the way it is written for users, e.g. `dd-mm-yyyy` for `%d-%m-%Y`
+pub fn date_format_hint(format: &str) -> String {
+    [
+        ("%F", "yyyy-mm-dd"),
+        ("%Y", "yyyy"),
+        ("%y", "yy"),
+        ("%m", "mm"),
+        ("%d", "dd"),
+        ("%e", "d"),
+        ("%B", "month"),
+        ("%b", "mon"),
+        ("%A", "weekday"),
+        ("%a", "wday"),
+    ]
+    .iter()
+    .fold(format.to_string(), |hint, (specifier, text)| hint.replace(specifier, text))
+}
+
+/// Display dates in `format` instead of the default one, it has no effect once
+/// a date has been displayed
+pub fn set_display_date_format(format: &str) {
+    let _ = DISPLAY_DATE_FORMAT.set(format.to_string());
+}
+
+/// Whether `format` is a strftime format a date can be written with, formats
+/// with a time or a timezone are not
+pub fn is_valid_date_format(format: &str) -> bool {
+    let mut formatted = String::new();
+    !format.trim().is_empty()
+        && StrftimeItems::new(format).all(|item| item != Item::Error)
+        && write!(formatted, "{}", NaiveDate::MIN.format(format)).is_ok()
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd)]
 pub struct TaskDate(pub Option<NaiveDate>);
 
@@ -26,3 +90,100 @@ impl TryFrom<TaskDate> for String {
         }
     }
 }
+
+static DEFAULT_TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Timezone of the tasks due at a time without an explicit timezone, times are
+/// displayed in it too. It is the one set with `set_default_timezone`, otherwise
+/// the zone named by the `TZ` environment variable, or the zone of the system,
+/// or UTC when neither is known
+pub fn default_timezone() -> Tz {
+    *DEFAULT_TIMEZONE.get_or_init(|| {
+        std::env::var("TZ")
+            .ok()
+            .and_then(|name| name.parse().ok())
+            .or_else(|| iana_time_zone::get_timezone().ok().and_then(|name| name.parse().ok()))
+            .unwrap_or(Tz::UTC)
+    })
+}
+
+/// Use `timezone` as the default timezone, it has no effect once the default
+/// timezone has been used
+pub fn set_default_timezone(timezone: Tz) {
+    let _ = DEFAULT_TIMEZONE.set(timezone);
+}
+
+/// Current day in `default_timezone()`
+pub fn today() -> NaiveDate {
+    Utc::now().with_timezone(&default_timezone()).date_naive()
+}
+
+/// When a task is due: a day, optionally a time of that day and the timezone of
+/// the time. Without a timezone the time is in `default_timezone()`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Due {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    pub timezone: Option<Tz>,
+}
+
+/// Parse a day in any format of `date_parser::parse_date`, optionally followed
+/// by a time `HH:MM` and a timezone name, e.g. `20-10-2026 09:30 Europe/Rome`
+/// or `next fri 09:30`
+impl FromStr for Due {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        let mut timezone = None;
+        if words.len() > 1 && words[words.len() - 2].contains(':') {
+            let name = words.pop().unwrap_or_default();
+            timezone = Some(
+                name.parse::<Tz>()
+                    .map_err(|_| anyhow!("Unknown timezone {name:?}, use a name like Europe/Rome"))?,
+            );
+        }
+        let time = match words.last().map(|word| NaiveTime::parse_from_str(word, TIME_FORMAT)) {
+            Some(Ok(time)) => {
+                words.pop();
+                Some(time)
+            }
+            _ => None,
+        };
+        let date = parse_date(&words.join(" "), today()).ok_or_else(|| {
+            anyhow!(
+                "Invalid date, use {} or words like tomorrow or next fri, optionally followed by HH:MM and a timezone",
+                date_format_hint(input_date_format())
+            )
+        })?;
+        Ok(Due { date, time, timezone })
+    }
+}
+
+/// Written in the same format it is parsed from
+impl Display for Due {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date.format(DATE_FORMAT))?;
+        if let Some(time) = self.time {
+            write!(f, " {}", time.format(TIME_FORMAT))?;
+        }
+        if let (Some(_), Some(timezone)) = (self.time, self.timezone) {
+            write!(f, " {}", timezone.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Format a point in time for display like `format_due`, in `default_timezone()`
+pub fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    let local = timestamp.with_timezone(&default_timezone());
+    format_due(local.date_naive(), Some(local.time()))
+}
+
+/// Format a day and its time, if any, for display in `display_date_format()`
+pub fn format_due(date: NaiveDate, time: Option<NaiveTime>) -> String {
+    match time {
+        Some(time) => format!("{} {}", date.format(display_date_format()), time.format(TIME_FORMAT)),
+        None => date.format(display_date_format()).to_string(),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::date::{input_date_format, DATE_FORMAT};
+use crate::recurrence::parse_weekday;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+/// A length of time in a relative date like `in 3 days` or `+2w`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+fn parse_unit(value: &str) -> Option<Unit> {
+    match value {
+        "d" | "day" | "days" => Some(Unit::Day),
+        "w" | "week" | "weeks" => Some(Unit::Week),
+        "m" | "month" | "months" => Some(Unit::Month),
+        "y" | "year" | "years" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+/// `amount` units after `today`, or before it when `amount` is negative
+fn shift(today: NaiveDate, amount: i64, unit: Unit) -> Option<NaiveDate> {
+    let count = u32::try_from(amount.unsigned_abs()).ok()?;
+    let forward = amount >= 0;
+    match unit {
+        Unit::Day | Unit::Week => {
+            let days = Days::new(match unit {
+                Unit::Week => u64::from(count) * 7,
+                _ => u64::from(count),
+            });
+            match forward {
+                true => today.checked_add_days(days),
+                false => today.checked_sub_days(days),
+            }
+        }
+        Unit::Month | Unit::Year => {
+            let months = Months::new(match unit {
+                Unit::Year => count.checked_mul(12)?,
+                _ => count,
+            });
+            match forward {
+                true => today.checked_add_months(months),
+                false => today.checked_sub_months(months),
+            }
+        }
+    }
+}
+
+/// Parse an offset from today with a sign and a unit letter, e.g. `+3d`, `-1w`, `+2m`
+fn parse_offset(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if !value.starts_with(['+', '-']) {
+        return None;
+    }
+    let digits = value[1..].find(|c: char| !c.is_ascii_digit())? + 1;
+    let amount: i64 = value[..digits].parse().ok()?;
+    shift(today, amount, parse_unit(&value[digits..])?)
+}
+
+/// First `weekday` after `today`, a week from today if today is that weekday
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today.checked_add_days(Days::new(if days == 0 { 7 } else { u64::from(days) }))
+}
+
+fn end_of_month(today: NaiveDate) -> Option<NaiveDate> {
+    today
+        .with_day(1)?
+        .checked_add_months(Months::new(1))?
+        .pred_opt()
+}
+
+/// Parse a day written as:
+/// - `dd-mm-yyyy`, ISO `yyyy-mm-dd` or the format of `date::input_date_format`
+/// - `today`, `tomorrow`, `yesterday`
+/// - a weekday name or its first letters, optionally after `next`: `fri`, `next friday`.
+///   It is the first such day after today
+/// - `in 3 days`, `in 2 weeks`, `in 1 month`, `in 1 year`, `next week`, `next month`, `next year`
+/// - days, weeks, months or years from today with a sign: `+3d`, `-2w`, `+1m`, `+1y`
+/// - `eow`, `eom`, `eoy` for the last day of this week, month and year
+///
+/// Relative dates count from `today`, words are case insensitive
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.to_lowercase();
+    let words: Vec<&str> = input.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.checked_add_days(Days::new(1)),
+        ["yesterday"] => today.checked_sub_days(Days::new(1)),
+        ["eow"] => today.checked_add_days(Days::new(u64::from(6 - today.weekday().num_days_from_monday()))),
+        ["eom"] => end_of_month(today),
+        ["eoy"] => NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        ["next", word] => match parse_unit(word) {
+            Some(unit) => shift(today, 1, unit),
+            None => next_weekday(today, parse_weekday(word)?),
+        },
+        ["in", amount, unit] => shift(today, amount.parse::<u32>().ok()?.into(), parse_unit(unit)?),
+        [word] => parse_offset(word, today)
+            .or_else(|| parse_weekday(word).and_then(|day| next_weekday(today, day)))
+            .or_else(|| NaiveDate::parse_from_str(word, DATE_FORMAT).ok())
+            .or_else(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()),
+        _ => None,
+    }
+    .or_else(|| NaiveDate::parse_from_str(&input, input_date_format()).ok())
+}
@@ -0,0 +1,34 @@
+use crate::query::ParseQueryError;
+use thiserror::Error;
+
+/// Errors returned by the storage layer (`DB` and `TasksService`)
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("Can't apply database migration {version}: {source}")]
+    Migration {
+        version: i64,
+        source: rusqlite::Error,
+    },
+    #[error("Database schema version {found} is newer than the supported version {supported}, please update task rustler")]
+    SchemaTooNew { found: i64, supported: i64 },
+    #[error("Invalid priority {0}")]
+    InvalidPriority(u8),
+    #[error("Invalid date {0:?}")]
+    InvalidDate(String),
+    #[error("Invalid recurrence rule {0:?}")]
+    InvalidRecurrence(String),
+    #[error("Unknown timezone {0:?}")]
+    InvalidTimezone(String),
+    #[error("The default project can't be deleted")]
+    DefaultProjectDeletion,
+    #[error("The default view can't be deleted")]
+    DefaultViewDeletion,
+    #[error("Invalid sort {0:?}")]
+    InvalidSort(String),
+    #[error(transparent)]
+    InvalidQuery(#[from] ParseQueryError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
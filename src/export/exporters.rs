@@ -1,9 +1,29 @@
-use icalendar::{Calendar, Component, EventLike, Todo, TodoStatus};
-use crate::task::Task;
+use std::collections::BTreeMap;
+use chrono::{Datelike, Utc};
+use chrono_tz::Tz;
+use icalendar::{Calendar, CalendarDateTime, Component, EventLike, Todo, TodoStatus};
+use crate::project::Project;
+use crate::export::timezones::vtimezone;
+use crate::export::UTC_DATE_TIME_FORMAT;
+use crate::task::{Priority, Task};
 
-pub fn export_tasks_to_icalendar(calendar_name: &'static str, tasks: &[Task]) -> Calendar {
+pub fn export_tasks_to_icalendar(calendar_name: &str, tasks: &[Task]) -> Calendar {
     let mut calendar = Calendar::new();
     calendar.name(calendar_name);
+    // the years each timezone is used in, the TZID of DUE refers to a VTIMEZONE
+    let mut timezones: BTreeMap<&str, (Tz, i32, i32)> = BTreeMap::new();
+    for due_at in tasks.iter().filter_map(|task| task.due_at()) {
+        let timezone = due_at.timezone();
+        if timezone != Tz::UTC {
+            let year = due_at.year();
+            let years = timezones.entry(timezone.name()).or_insert((timezone, year, year));
+            years.1 = years.1.min(year);
+            years.2 = years.2.max(year);
+        }
+    }
+    for (timezone, first_year, last_year) in timezones.into_values() {
+        calendar.push(vtimezone(timezone, first_year..=last_year));
+    }
     for task in tasks {
         calendar.push(
             build_icalendar_todo_task(task)
@@ -13,11 +33,89 @@ pub fn export_tasks_to_icalendar(calendar_name: &'static str, tasks: &[Task]) ->
     calendar.done()
 }
 
+/// Build one calendar for each project, named after it and containing only its tasks.
+/// Calendars are returned in the same order as `projects`
+pub fn export_projects_to_icalendar(projects: &[Project], tasks: &[Task]) -> Vec<Calendar> {
+    projects
+        .iter()
+        .map(|project| {
+            let project_tasks: Vec<Task> = tasks
+                .iter()
+                .filter(|task| task.project_id == project.id)
+                .cloned()
+                .collect();
+            export_tasks_to_icalendar(project.name.as_str(), &project_tasks)
+        })
+        .collect()
+}
+
+/// Names of the files the calendars of `projects` are written to, in the same order:
+/// `<project name>.ics`, with the id of the project added when the name would clash
+/// with the file of another project, ignoring case for case-insensitive file systems
+pub fn calendar_file_names(projects: &[Project]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for project in projects {
+        let clashes = |name: &str| {
+            projects
+                .iter()
+                .any(|other| other.id != project.id && other.file_name().eq_ignore_ascii_case(name))
+        };
+        let mut name = match clashes(&project.file_name()) {
+            true => format!("{}-{}", project.file_name(), project.id),
+            false => project.file_name(),
+        };
+        // a project could be named like the file of another one with its id
+        while names.iter().any(|taken| taken.eq_ignore_ascii_case(&format!("{name}.ics"))) {
+            name.push('_');
+        }
+        names.push(format!("{name}.ics"));
+    }
+    names
+}
+
 fn build_icalendar_todo_task(task: &Task) -> Todo {
     let mut todo = Todo::new();
-    if let Some(date) = task.date.0{
-        todo.all_day(date);
-    };
+    // a stable UID lets calendars update the entry exported before instead of adding a copy
+    if !task.uid.is_empty() {
+        todo.uid(task.uid.as_str());
+    }
+    if let Some(created_at) = task.created_at {
+        todo.created(created_at);
+    }
+    if let Some(updated_at) = task.updated_at {
+        todo.timestamp(updated_at);
+        // `Component::last_modified` writes the property name as LAST_MODIFIED
+        todo.add_property("LAST-MODIFIED", updated_at.format(UTC_DATE_TIME_FORMAT).to_string());
+    }
+    let due_at = task.due_at().map(|due_at| match due_at.timezone() {
+        Tz::UTC => CalendarDateTime::Utc(due_at.with_timezone(&Utc)),
+        timezone => CalendarDateTime::WithTimezone {
+            date_time: due_at.naive_local(),
+            tzid: timezone.name().to_string(),
+        },
+    });
+    match (&due_at, task.date.0) {
+        (Some(due_at), _) => {
+            todo.due(due_at.clone());
+        }
+        (None, Some(date)) => {
+            todo.all_day(date);
+        }
+        (None, None) => {}
+    }
+    if let Some(rule) = task.recurrence.as_ref().and_then(|r| r.rrule()) {
+        // recurrence instances are computed starting from DTSTART
+        match (due_at, task.date.0) {
+            (Some(due_at), _) => {
+                todo.starts(due_at);
+            }
+            (None, Some(date)) => {
+                todo.starts(date);
+            }
+            (None, None) => {}
+        }
+        todo.add_property("RRULE", rule);
+    }
     todo.summary(task.title.as_str());
     todo.description(task.description.as_str());
     if task.completed {
@@ -25,6 +123,16 @@ fn build_icalendar_todo_task(task: &Task) -> Todo {
     } else {
         todo.status(TodoStatus::NeedsAction);
     }
-    todo.priority(task.priority.to_u8() as u32);
+    todo.priority(priority_to_icalendar(&task.priority));
     todo.done()
+}
+
+/// The iCalendar PRIORITY of a task: 1, 5 and 9, which rfc5545 clients read as high,
+/// medium and low
+fn priority_to_icalendar(priority: &Priority) -> u32 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use crate::export::UTC_DATE_TIME_FORMAT;
+use icalendar::{Calendar, CalendarComponent, CalendarDateTime, Component, DatePerhapsTime, Todo, TodoStatus};
+use crate::date::Due;
+use crate::recurrence::Recurrence;
+use crate::task::{Priority, Task};
+
+/// Tasks read from a calendar, together with a description of every component
+/// that couldn't be turned into a task
+#[derive(Debug, Default)]
+pub struct ImportedTasks {
+    pub tasks: Vec<Task>,
+    pub skipped: Vec<String>,
+}
+
+/// Read the VTODO components of an iCalendar file into tasks. Other components
+/// (events, venues, ...) and to-dos without a summary are skipped and reported.
+/// Imported tasks have id 0 and belong to the default project, UID, CREATED and
+/// LAST-MODIFIED are kept, so are the time and timezone of DUE
+pub fn import_tasks_from_icalendar(content: &str) -> Result<ImportedTasks> {
+    // the parser is lenient and reads anything as an empty calendar
+    if !content.trim_start().to_uppercase().starts_with("BEGIN:VCALENDAR") {
+        return Err(anyhow!("Invalid iCalendar file: missing BEGIN:VCALENDAR"));
+    }
+    let calendar: Calendar = content
+        .parse()
+        .map_err(|e| anyhow!("Invalid iCalendar file: {}", e))?;
+    let mut imported = ImportedTasks::default();
+    for component in &calendar.components {
+        match component {
+            CalendarComponent::Todo(todo) => match todo_to_task(todo) {
+                Some(task) => imported.tasks.push(task),
+                None => imported.skipped.push("VTODO without a summary".to_string()),
+            },
+            CalendarComponent::Event(event) => imported.skipped.push(format!(
+                "VEVENT {:?}",
+                event.get_summary().unwrap_or_default()
+            )),
+            CalendarComponent::Venue(_) => imported.skipped.push("VVENUE".to_string()),
+            _ => imported.skipped.push("unknown component".to_string()),
+        }
+    }
+    Ok(imported)
+}
+
+fn todo_to_task(todo: &Todo) -> Option<Task> {
+    let title = todo.get_summary()?.trim();
+    if title.is_empty() {
+        return None;
+    }
+    let mut task = Task::new();
+    task.title = title.to_string();
+    task.description = todo.get_description().unwrap_or_default().to_string();
+    task.uid = todo.get_uid().unwrap_or_default().to_string();
+    task.created_at = todo.get_created();
+    task.updated_at = todo
+        .property_value("LAST-MODIFIED")
+        .and_then(|value| NaiveDateTime::parse_from_str(value, UTC_DATE_TIME_FORMAT).ok())
+        .map(|date_time| date_time.and_utc())
+        .or_else(|| todo.get_last_modified());
+    task.completed =
+        todo.get_status() == Some(TodoStatus::Completed) || todo.get_completed().is_some();
+    if let Some(priority) = todo.get_priority().and_then(priority_from_icalendar) {
+        task.priority = priority;
+    }
+    task.set_due(todo.get_due().or_else(|| todo.get_start()).map(to_due));
+    // rules that can't be represented are dropped, the task is imported as not repeating
+    task.recurrence = todo
+        .property_value("RRULE")
+        .and_then(|rule| Recurrence::from_rrule(rule).ok());
+    Some(task)
+}
+
+/// Map an iCalendar PRIORITY to a task priority following rfc5545: 1 to 4 are
+/// high, 5 medium, 6 to 9 low. 0 means undefined, the task keeps the default priority
+fn priority_from_icalendar(value: u32) -> Option<Priority> {
+    match value {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Floating times, and times in a zone that is not an IANA name, are taken in the default timezone
+fn to_due(date: DatePerhapsTime) -> Due {
+    let (date_time, timezone) = match date {
+        DatePerhapsTime::Date(date) => {
+            return Due { date, time: None, timezone: None };
+        }
+        DatePerhapsTime::DateTime(CalendarDateTime::Floating(date_time)) => (date_time, None),
+        DatePerhapsTime::DateTime(CalendarDateTime::Utc(date_time)) => (date_time.naive_utc(), Some(Tz::UTC)),
+        DatePerhapsTime::DateTime(CalendarDateTime::WithTimezone { date_time, tzid }) => {
+            (date_time, tzid.parse::<Tz>().ok())
+        }
+    };
+    Due {
+        date: date_time.date(),
+        time: Some(date_time.time()),
+        timezone,
+    }
+}
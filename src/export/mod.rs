@@ -1,5 +1,11 @@
 mod exporters;
 mod file_writer;
+mod importers;
+mod timezones;
 
-pub use exporters::export_tasks_to_icalendar;
-pub use file_writer::write_to_file;
\ No newline at end of file
+/// Format of the UTC date-time values, e.g. LAST-MODIFIED
+const UTC_DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub use exporters::{calendar_file_names, export_projects_to_icalendar, export_tasks_to_icalendar};
+pub use file_writer::write_to_file;
+pub use importers::{import_tasks_from_icalendar, ImportedTasks};
@@ -0,0 +1,149 @@
+use std::ops::RangeInclusive;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use icalendar::CalendarComponent;
+
+/// Offset of a timezone between two of its transitions
+#[derive(Clone, PartialEq)]
+struct Observance {
+    /// Seconds east of UTC
+    offset: i32,
+    daylight: bool,
+    name: Option<String>,
+}
+
+/// Moment, in UTC, at which a timezone moves from an observance to another
+struct Transition {
+    at: NaiveDateTime,
+    from: Observance,
+    to: Observance,
+}
+
+impl Transition {
+    /// Local time of the transition, in the observance it ends
+    fn local(&self) -> NaiveDateTime {
+        self.at + Duration::seconds(self.from.offset.into())
+    }
+}
+
+/// A VTIMEZONE component describing `timezone` in `years`, so DUE can be written
+/// with its TZID. The observances of the last year repeat yearly when the rules of
+/// the zone don't change the year after, that covers the occurrences of recurring tasks
+pub fn vtimezone(timezone: Tz, years: RangeInclusive<i32>) -> CalendarComponent {
+    let first_year = *years.start();
+    let last_year = *years.end();
+    let start = NaiveDate::from_ymd_opt(first_year, 1, 1).unwrap().and_time(NaiveTime::MIN);
+    let initial = observance(timezone, start);
+    let transitions = transitions(timezone, first_year..=last_year + 1);
+    let next_year: Vec<&Transition> = transitions.iter().filter(|t| t.at.year() > last_year).collect();
+
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", timezone.name())];
+    lines.extend(observance_lines(&initial, &initial, start + Duration::seconds(initial.offset.into()), None));
+    for transition in transitions.iter().filter(|t| t.at.year() <= last_year) {
+        let rule = match transition.at.year() == last_year {
+            true => yearly_rule(transition, &next_year),
+            false => None,
+        };
+        lines.extend(observance_lines(&transition.from, &transition.to, transition.local(), rule));
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    // the component is built from text since icalendar can't create components other than
+    // events, to-dos and venues. It adds a DTSTAMP and a UID when writing it, which rfc5545
+    // allows as IANA properties and clients ignore
+    format!("{}\r\n", lines.join("\r\n")).parse().unwrap()
+}
+
+fn observance(timezone: Tz, at: NaiveDateTime) -> Observance {
+    let offset = timezone.offset_from_utc_datetime(&at);
+    Observance {
+        offset: offset.fix().local_minus_utc(),
+        daylight: !offset.dst_offset().is_zero(),
+        name: offset.abbreviation().map(str::to_string),
+    }
+}
+
+/// Transitions of `timezone` in `years`, looked for hour by hour then to the minute
+fn transitions(timezone: Tz, years: RangeInclusive<i32>) -> Vec<Transition> {
+    let mut at = NaiveDate::from_ymd_opt(*years.start(), 1, 1).unwrap().and_time(NaiveTime::MIN);
+    let end = NaiveDate::from_ymd_opt(*years.end() + 1, 1, 1).unwrap().and_time(NaiveTime::MIN);
+    let mut current = observance(timezone, at);
+    let mut transitions = vec![];
+    while at < end {
+        let next = at + Duration::hours(1);
+        let observed = observance(timezone, next);
+        if observed != current {
+            let (mut before, mut after) = (0, 60);
+            while after - before > 1 {
+                let middle = (before + after) / 2;
+                match observance(timezone, at + Duration::minutes(middle)) == current {
+                    true => before = middle,
+                    false => after = middle,
+                }
+            }
+            transitions.push(Transition {
+                at: at + Duration::minutes(after),
+                from: current,
+                to: observed.clone(),
+            });
+            current = observed;
+        }
+        at = next;
+    }
+    transitions
+}
+
+/// RRULE repeating `transition` every year, when it describes the transition between
+/// the same observances in `next_year` too
+fn yearly_rule(transition: &Transition, next_year: &[&Transition]) -> Option<String> {
+    let local = transition.local();
+    let next = next_year
+        .iter()
+        .find(|next| next.from == transition.from && next.to == transition.to)?
+        .local();
+    if next.month() != local.month() || next.weekday() != local.weekday() || next.time() != local.time() {
+        return None;
+    }
+    let weekday = &local.weekday().to_string()[..2].to_uppercase();
+    let ordinal = |date: NaiveDateTime| (date.day() - 1) / 7 + 1;
+    let is_last = |date: NaiveDateTime| (date + Duration::days(7)).month() != date.month();
+    let by_day = if is_last(local) && is_last(next) {
+        format!("-1{weekday}")
+    } else if ordinal(local) == ordinal(next) {
+        format!("{}{weekday}", ordinal(local))
+    } else {
+        return None;
+    };
+    Some(format!("FREQ=YEARLY;BYMONTH={};BYDAY={by_day}", local.month()))
+}
+
+fn observance_lines(from: &Observance, to: &Observance, start: NaiveDateTime, rule: Option<String>) -> Vec<String> {
+    let kind = match to.daylight {
+        true => "DAYLIGHT",
+        false => "STANDARD",
+    };
+    let mut lines = vec![
+        format!("BEGIN:{kind}"),
+        format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+        format!("TZOFFSETFROM:{}", format_offset(from.offset)),
+        format!("TZOFFSETTO:{}", format_offset(to.offset)),
+    ];
+    if let Some(name) = &to.name {
+        lines.push(format!("TZNAME:{name}"));
+    }
+    if let Some(rule) = rule {
+        lines.push(format!("RRULE:{rule}"));
+    }
+    lines.push(format!("END:{kind}"));
+    lines
+}
+
+/// UTC offset as `+HHMM`, with the seconds when there are some
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let (hours, minutes, seconds) = (offset / 3600, offset / 60 % 60, offset % 60);
+    match seconds {
+        0 => format!("{sign}{hours:02}{minutes:02}"),
+        _ => format!("{sign}{hours:02}{minutes:02}{seconds:02}"),
+    }
+}
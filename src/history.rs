@@ -0,0 +1,111 @@
+use crate::error::Result;
+use crate::task::Task;
+use crate::task_manager::TasksService;
+
+/// Maximum number of changes that can be undone
+const MAX_HISTORY: usize = 100;
+
+/// A change to the tasks, stored as the state of the tasks it touched before
+/// and after it. Tasks missing in one of the two states were added by the change,
+/// deleted tasks are in the trash in the state after it. Parents always come
+/// before their subtasks.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Change {
+    pub description: String,
+    pub before: Vec<Task>,
+    pub after: Vec<Task>,
+}
+
+impl Change {
+    pub fn new(description: &str, before: Vec<Task>, after: Vec<Task>) -> Self {
+        Self {
+            description: description.to_string(),
+            before,
+            after,
+        }
+    }
+
+    /// Bring the tasks from the `from` state to the `to` state
+    fn apply(service: &TasksService, from: &[Task], to: &[Task]) -> Result<()> {
+        for task in from.iter().rev() {
+            if !to.iter().any(|t| t.id == task.id) {
+                service.remove_task(task.id)?;
+            }
+        }
+        for task in to {
+            service.restore_task(task)?;
+        }
+        Ok(())
+    }
+}
+
+/// Undo/redo history of the changes made to the tasks
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a change that has just been made, the redo history is discarded
+    pub fn record(&mut self, change: Change) {
+        if change.before == change.after {
+            return;
+        }
+        self.undo.push(change);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Descriptions of the changes that touched the task `id` and can be undone, latest first
+    pub fn changes_of(&self, id: i32) -> Vec<&str> {
+        self.undo
+            .iter()
+            .rev()
+            .filter(|change| change.before.iter().chain(&change.after).any(|task| task.id == id))
+            .map(|change| change.description.as_str())
+            .collect()
+    }
+
+    /// Revert the last change, returns its description or None if there is nothing to undo
+    pub fn undo(&mut self, service: &TasksService) -> Result<Option<String>> {
+        let Some(change) = self.undo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = Change::apply(service, &change.after, &change.before) {
+            self.undo.push(change);
+            return Err(e);
+        }
+        let description = change.description.clone();
+        self.redo.push(change);
+        Ok(Some(description))
+    }
+
+    /// Apply again the last undone change, returns its description or None if there is nothing to redo
+    pub fn redo(&mut self, service: &TasksService) -> Result<Option<String>> {
+        let Some(change) = self.redo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = Change::apply(service, &change.before, &change.after) {
+            self.redo.push(change);
+            return Err(e);
+        }
+        let description = change.description.clone();
+        self.undo.push(change);
+        Ok(Some(description))
+    }
+}
@@ -0,0 +1,499 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// What a key sequence does in the tasks list, named in the `[keys]` table of
+/// the config file, e.g. `add_task = "n"`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    AddTask,
+    AddSubtask,
+    EditTask,
+    ToggleStatus,
+    TogglePriority,
+    DeleteTask,
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    /// Move the selection down by half the height of the list
+    HalfPageDown,
+    HalfPageUp,
+    Collapse,
+    Expand,
+    SortByPriority,
+    SortByDate,
+    NextProject,
+    PreviousProject,
+    AddProject,
+    Search,
+    Filter,
+    PickView,
+    Undo,
+    Redo,
+    Export,
+    Import,
+    /// Show the deleted tasks, to restore or purge them
+    Trash,
+    /// Move the selected completed task to the archive
+    ArchiveTask,
+    /// Show the archived tasks, to take them out of the archive
+    Archive,
+    /// Hide the completed tasks from the list, or show them again
+    ToggleCompleted,
+    /// Type a command like `:sort date`, see `LineCommand`
+    CommandLine,
+    /// Show or hide the pane with every field of the selected task
+    ToggleDetails,
+    ToggleHelp,
+    /// Close the help, or clear the search, or clear the filter
+    Cancel,
+    Quit,
+}
+
+impl Action {
+    /// Every action, in the order of the help
+    pub const ALL: [Action; 35] = [
+        Action::AddTask,
+        Action::AddSubtask,
+        Action::EditTask,
+        Action::ToggleStatus,
+        Action::TogglePriority,
+        Action::DeleteTask,
+        Action::SelectNext,
+        Action::SelectPrevious,
+        Action::SelectFirst,
+        Action::SelectLast,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::Collapse,
+        Action::Expand,
+        Action::SortByPriority,
+        Action::SortByDate,
+        Action::NextProject,
+        Action::PreviousProject,
+        Action::AddProject,
+        Action::Search,
+        Action::Filter,
+        Action::PickView,
+        Action::Undo,
+        Action::Redo,
+        Action::Export,
+        Action::Import,
+        Action::Trash,
+        Action::ArchiveTask,
+        Action::Archive,
+        Action::ToggleCompleted,
+        Action::CommandLine,
+        Action::ToggleDetails,
+        Action::ToggleHelp,
+        Action::Cancel,
+        Action::Quit,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::AddTask => "add a task",
+            Action::AddSubtask => "add a subtask to the selected task",
+            Action::EditTask => "modify the selected task",
+            Action::ToggleStatus => "toggle status",
+            Action::TogglePriority => "change the priority",
+            Action::DeleteTask => "delete the selected task",
+            Action::SelectNext => "select the next task",
+            Action::SelectPrevious => "select the previous task",
+            Action::SelectFirst => "select the first task",
+            Action::SelectLast => "select the last task",
+            Action::HalfPageDown => "move down half a page",
+            Action::HalfPageUp => "move up half a page",
+            Action::Collapse => "collapse subtasks",
+            Action::Expand => "expand subtasks",
+            Action::SortByPriority => "sort by priority",
+            Action::SortByDate => "sort by date",
+            Action::NextProject => "switch to the next project",
+            Action::PreviousProject => "switch to the previous project",
+            Action::AddProject => "create a new project",
+            Action::Search => "search",
+            Action::Filter => "filter the tasks",
+            Action::PickView => "pick a saved view",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Export => "export the tasks to .ics file",
+            Action::Import => "import the tasks of an .ics file",
+            Action::Trash => "open the trash to restore deleted tasks",
+            Action::ArchiveTask => "archive the selected completed task",
+            Action::Archive => "open the archive of completed tasks",
+            Action::ToggleCompleted => "hide or show the completed tasks",
+            Action::CommandLine => "type a command, e.g. :sort date",
+            Action::ToggleDetails => "show or hide the details of the selected task",
+            Action::ToggleHelp => "show or hide this help",
+            Action::Cancel => "close the help, clear the search or the filter",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// Whether a count typed before the keys, like the 5 of `5j`, runs the action
+    /// that many times
+    pub fn repeats(&self) -> bool {
+        matches!(
+            self,
+            Action::SelectNext
+                | Action::SelectPrevious
+                | Action::HalfPageDown
+                | Action::HalfPageUp
+                | Action::DeleteTask
+                | Action::TogglePriority
+                | Action::NextProject
+                | Action::PreviousProject
+                | Action::Undo
+                | Action::Redo
+        )
+    }
+
+    fn default_keys(&self, preset: KeyPreset) -> &'static [&'static str] {
+        if preset == KeyPreset::Vim {
+            if let Some(keys) = self.vim_keys() {
+                return keys;
+            }
+        }
+        match self {
+            Action::AddTask => &["a"],
+            Action::AddSubtask => &["A"],
+            Action::EditTask => &["m"],
+            Action::ToggleStatus => &["space"],
+            Action::TogglePriority => &["p"],
+            Action::DeleteTask => &["ctrl+d"],
+            Action::SelectNext => &["down"],
+            Action::SelectPrevious => &["up"],
+            Action::SelectFirst => &["gg", "home"],
+            Action::SelectLast => &["G", "end"],
+            Action::HalfPageDown => &["pagedown"],
+            Action::HalfPageUp => &["pageup"],
+            Action::Collapse => &["left"],
+            Action::Expand => &["right"],
+            Action::SortByPriority => &["s"],
+            Action::SortByDate => &["t"],
+            Action::NextProject => &["tab"],
+            Action::PreviousProject => &["shift+tab"],
+            Action::AddProject => &["ctrl+n"],
+            Action::Search => &["/"],
+            Action::Filter => &["f"],
+            Action::PickView => &["v"],
+            Action::Undo => &["u"],
+            Action::Redo => &["ctrl+r"],
+            Action::Export => &["ctrl+e"],
+            Action::Import => &["ctrl+o"],
+            Action::Trash => &["T"],
+            Action::ArchiveTask => &["z"],
+            Action::Archive => &["Z"],
+            Action::ToggleCompleted => &["c"],
+            Action::CommandLine => &[":"],
+            Action::ToggleDetails => &["enter"],
+            Action::ToggleHelp => &["h"],
+            Action::Cancel => &["esc"],
+            Action::Quit => &["ctrl+q"],
+        }
+    }
+
+    /// Keys of the vim preset that differ from the default ones
+    fn vim_keys(&self) -> Option<&'static [&'static str]> {
+        Some(match self {
+            Action::AddTask => &["o", "a"],
+            Action::EditTask => &["i", "m"],
+            Action::ToggleStatus => &["x", "space"],
+            Action::DeleteTask => &["dd"],
+            Action::SelectNext => &["j", "down"],
+            Action::SelectPrevious => &["k", "up"],
+            Action::HalfPageDown => &["ctrl+d", "pagedown"],
+            Action::HalfPageUp => &["ctrl+u", "pageup"],
+            Action::Collapse => &["h", "left"],
+            Action::Expand => &["l", "right"],
+            Action::NextProject => &["gt", "tab"],
+            Action::PreviousProject => &["gT", "shift+tab"],
+            Action::ToggleHelp => &["?"],
+            _ => return None,
+        })
+    }
+}
+
+/// Set of default keys the `[keys]` of the config file change
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPreset {
+    #[default]
+    Default,
+    /// `j`/`k` to move, `dd` to delete, `x` to toggle, `?` for help and so on
+    Vim,
+}
+
+/// A key with its modifiers. Shift is part of the character, `A` is Shift + a
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            // terminals report Shift for upper case letters and some symbols, and
+            // with Shift + Tab
+            KeyCode::Char(_) | KeyCode::BackTab => Self { code, modifiers: modifiers - KeyModifiers::SHIFT },
+            _ => Self { code, modifiers },
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseKeyError(pub String);
+
+impl Display for ParseKeyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid key binding: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
+const NAMED_KEYS: [(&str, KeyCode); 17] = [
+    ("space", KeyCode::Char(' ')),
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("minus", KeyCode::Char('-')),
+];
+
+/// Parse a key name, a function key like `f5` or a single character
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let lowercase = name.to_lowercase();
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(key, _)| *key == lowercase) {
+        return Some(*code);
+    }
+    if let Some(number) = lowercase.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=12).contains(&number).then_some(KeyCode::F(number));
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ => None,
+    }
+}
+
+/// Parse one word of a key sequence: a key with modifiers like `ctrl+r`,
+/// `shift+tab`, a key name or characters typed one after the other like `gg`
+fn parse_word(word: &str) -> Result<Vec<KeyChord>, ParseKeyError> {
+    let err = || ParseKeyError(format!("unknown key {word:?}"));
+    if let Some(code) = parse_key_code(word) {
+        return Ok(vec![KeyChord::new(code, KeyModifiers::NONE)]);
+    }
+    if let Some((modifiers, key)) = word.rsplit_once('+').filter(|(modifiers, _)| !modifiers.is_empty()) {
+        let mut code = parse_key_code(key).ok_or_else(err)?;
+        let mut chord_modifiers = KeyModifiers::NONE;
+        for modifier in modifiers.split('+') {
+            match (modifier.to_lowercase().as_str(), code) {
+                ("ctrl" | "control", _) => chord_modifiers |= KeyModifiers::CONTROL,
+                ("alt", _) => chord_modifiers |= KeyModifiers::ALT,
+                ("shift", KeyCode::Tab) => code = KeyCode::BackTab,
+                ("shift", KeyCode::Char(c)) => code = KeyCode::Char(c.to_ascii_uppercase()),
+                ("shift", _) => chord_modifiers |= KeyModifiers::SHIFT,
+                _ => return Err(ParseKeyError(format!("unknown modifier {modifier:?} in {word:?}"))),
+            }
+        }
+        return Ok(vec![KeyChord::new(code, chord_modifiers)]);
+    }
+    Ok(word
+        .chars()
+        .map(|c| KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE))
+        .collect())
+}
+
+/// Keys pressed one after the other, like `g g` or `gg`
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl FromStr for KeySequence {
+    type Err = ParseKeyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut chords = vec![];
+        for word in input.split_whitespace() {
+            chords.extend(parse_word(word)?);
+        }
+        if chords.is_empty() {
+            return Err(ParseKeyError("empty key".to_string()));
+        }
+        Ok(KeySequence(chords))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift + ")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::BackTab => write!(f, "Shift + Tab"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// Written as it is typed, characters are joined: `gg`, `Ctrl + r`
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let plain = |chord: &KeyChord| matches!(chord.code, KeyCode::Char(c) if c != ' ') && chord.modifiers.is_empty();
+        for (index, chord) in self.0.iter().enumerate() {
+            if index > 0 && !(plain(chord) && plain(&self.0[index - 1])) {
+                write!(f, " ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Keys of an action in the config file, one sequence or a list of them
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn sequences(&self) -> &[String] {
+        match self {
+            Keys::One(keys) => std::slice::from_ref(keys),
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+/// Outcome of the keys pressed so far
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KeyMatch {
+    Action(Action),
+    /// The keys start a longer sequence
+    Pending,
+    Unbound,
+}
+
+/// Key sequences of the actions of the tasks list
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(KeySequence, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(KeyPreset::Default, &BTreeMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// The bindings of `preset` with the keys of the actions in `overrides` replaced.
+    /// Default bindings clashing with an override are dropped, overrides clashing
+    /// with each other are an error: a sequence can't be bound twice, or start
+    /// another sequence
+    pub fn new(preset: KeyPreset, overrides: &BTreeMap<Action, Keys>) -> Result<Keymap, ParseKeyError> {
+        let mut bindings = vec![];
+        for (action, keys) in overrides {
+            for keys in keys.sequences() {
+                bindings.push((keys.parse::<KeySequence>()?, *action));
+            }
+        }
+        for (index, (sequence, action)) in bindings.iter().enumerate() {
+            if let Some((other, other_action)) = bindings[..index].iter().find(|(other, _)| clash(sequence, other)) {
+                return Err(ParseKeyError(format!(
+                    "{sequence} of {} clashes with {other} of {}",
+                    action_name(*action),
+                    action_name(*other_action)
+                )));
+            }
+        }
+        for action in Action::ALL.iter().filter(|action| !overrides.contains_key(action)) {
+            for keys in action.default_keys(preset) {
+                let sequence = keys.parse::<KeySequence>()?;
+                if !bindings.iter().any(|(other, _)| clash(&sequence, other)) {
+                    bindings.push((sequence, *action));
+                }
+            }
+        }
+        Ok(Keymap { bindings })
+    }
+
+    /// Match the keys pressed so far, `pressed` is never empty
+    pub fn lookup(&self, pressed: &[KeyChord]) -> KeyMatch {
+        let mut result = KeyMatch::Unbound;
+        for (sequence, action) in &self.bindings {
+            if sequence.0 == pressed {
+                return KeyMatch::Action(*action);
+            }
+            if sequence.0.starts_with(pressed) {
+                result = KeyMatch::Pending;
+            }
+        }
+        result
+    }
+
+    /// Key sequences bound to `action`
+    pub fn keys(&self, action: Action) -> Vec<&KeySequence> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(sequence, _)| sequence)
+            .collect()
+    }
+}
+
+/// Whether one sequence is the other or starts it, then the longer one can't be typed
+fn clash(a: &KeySequence, b: &KeySequence) -> bool {
+    a.0.starts_with(&b.0) || b.0.starts_with(&a.0)
+}
+
+/// Name of `action` in the config file
+fn action_name(action: Action) -> String {
+    let name = format!("{action:?}");
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
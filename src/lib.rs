@@ -1,8 +1,23 @@
 pub mod app;
+pub mod cli;
 pub mod command;
+pub mod command_line;
+pub mod config;
 pub mod date;
+pub mod date_parser;
+pub mod error;
+pub mod keymap;
+pub mod markdown;
+pub mod migrations;
+pub mod project;
+pub mod query;
+pub mod recurrence;
+pub mod search;
 pub mod task;
 pub mod task_db;
 pub mod task_manager;
+pub mod text_input;
 pub mod ui;
+pub mod view;
 pub mod export;
+pub mod history;
@@ -1,17 +1,64 @@
+use anyhow::Context;
 use ratatui::crossterm::event;
-use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::crossterm::event::{DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::crossterm::execute;
 use ratatui::Terminal;
 use std::error::Error;
+use std::fs;
 use std::io;
-use task_rustler::app::{AppContext, InputMode};
+use task_rustler::app::{AppContext, InputFieldType, InputMode};
+use task_rustler::cli;
 use task_rustler::command::*;
+use task_rustler::config::Config;
+use task_rustler::keymap::{Action, KeyChord, KeyMatch};
+use task_rustler::task_manager::TasksService;
 use task_rustler::ui;
 
+/// Highest count typed before an action
+const MAX_COUNT: usize = 9999;
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut app = AppContext::new(String::from("tasks.db"));
-    app.refresh_task_list();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parsed = cli::parse_global_options(&args)
+        .and_then(|(options, rest)| Ok((options, cli::parse_args(rest)?)));
+    let (options, command) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("taskrs: {err:#}");
+            std::process::exit(2);
+        }
+    };
+    let config = match load_config(&options) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("taskrs: {err:#}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(command) = command {
+        let res = TasksService::new(config.database.display().to_string())
+            .map_err(anyhow::Error::from)
+            .and_then(|service| {
+                service.purge_expired_trash(config.trash_retention_days)?;
+                service.archive_completed(config.archive_after_days)?;
+                cli::run(command, &service, &config, &mut io::stdout())
+            });
+        if let Err(err) = res {
+            eprintln!("taskrs: {err:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Open the database before entering raw mode, so errors are readable
+    let mut app = AppContext::with_config(config)?;
+    app.refresh_task_list()?;
     let mut terminal = ratatui::init();
+    // pasted text arrives as a whole instead of one key at a time
+    execute!(io::stdout(), EnableBracketedPaste)?;
     let res = run_app(&mut terminal, app);
+    let _ = execute!(io::stdout(), DisableBracketedPaste);
     ratatui::restore();
 
     if let Err(err) = res {
@@ -20,6 +67,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Read the config file, apply it and create the directory of the database
+fn load_config(options: &cli::GlobalOptions) -> anyhow::Result<Config> {
+    let mut config = Config::load(options.config.as_deref())?;
+    if let Some(db) = &options.db {
+        config.database = db.clone();
+    }
+    config.apply();
+    if let Some(dir) = config.database.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Can't create the database directory {}", dir.display()))?;
+    }
+    Ok(config)
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     mut app: AppContext,
@@ -27,127 +88,382 @@ fn run_app<B: ratatui::backend::Backend>(
     loop {
         terminal.draw(|f| ui::ui(f, &mut app))?;
 
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        if let (Event::Paste(text), None) = (&event, &app.confirmation) {
+            handle_paste(text, &mut app);
+        }
+        if let Event::Key(key) = event {
             // Capture only press key event
             if key.kind == event::KeyEventKind::Release {
                 continue;
             }
+            if app.confirmation.is_some() {
+                handle_key_event_confirmation(key.code, &mut app);
+                continue;
+            }
             match app.input_mode {
-                InputMode::View => match (key.code, key.modifiers) {
-                    (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
-                        return Ok(());
-                    }
-                    (KeyCode::Char('h'), KeyModifiers::NONE) => {
-                        app.show_help = !app.show_help;
-                    }
-                    (KeyCode::Esc, KeyModifiers::NONE) => {
-                        if app.show_help {
-                            app.show_help = false;
-                        }
-                    }
-                    _ => handle_key_event_view_mode(key, &mut app),
-                },
-                InputMode::Adding => handle_key_event_adding_mode(key.code, &mut app),
-                InputMode::EditingExisting => handle_key_event_editing_existing_mode(key.code, &mut app),
-                InputMode::Export => handle_key_event_export_mode(key.code, &mut app),
+                InputMode::View => handle_key_event_view_mode(key, &mut app),
+                InputMode::Adding => handle_key_event_adding_mode(key, &mut app),
+                InputMode::EditingExisting => handle_key_event_editing_existing_mode(key, &mut app),
+                InputMode::Export => handle_key_event_export_mode(key, &mut app),
+                InputMode::Import => handle_key_event_import_mode(key, &mut app),
+                InputMode::Search => handle_key_event_search_mode(key.code, &mut app),
+                InputMode::Filter => handle_key_event_filter_mode(key.code, &mut app),
+                InputMode::PickingView => handle_key_event_view_picker_mode(key.code, &mut app),
+                InputMode::AddingView => handle_key_event_adding_view_mode(key.code, &mut app),
+                InputMode::AddingProject => handle_key_event_adding_project_mode(key.code, &mut app),
+                InputMode::DeleteTaskWithSubtasks => handle_key_event_delete_subtasks_mode(key.code, &mut app),
+                InputMode::CommandLine => handle_key_event_command_line_mode(key.code, &mut app),
+                InputMode::Trash => handle_key_event_trash_mode(key.code, &mut app),
+                InputMode::Archive => handle_key_event_archive_mode(key.code, &mut app),
+            }
+            if app.quit {
+                return Ok(());
             }
         }
     }
 }
 
+/// Run the action bound to the keys typed so far, digits typed before them are
+/// a count, like the 5 of `5j`
 fn handle_key_event_view_mode(key: KeyEvent, app: &mut AppContext) {
-    match (key.code, key.modifiers) {
-        (KeyCode::Char('a'), KeyModifiers::NONE) => {
-            let _ = EnterAddModeCommand.execute(app);
+    let chord = KeyChord::from(key);
+    if let (true, KeyCode::Char(digit @ '0'..='9')) = (app.pending_keys.is_empty(), chord.code) {
+        // digits count unless they are bound, 0 only continues a count
+        let counting = chord.modifiers.is_empty()
+            && (digit != '0' || app.pending_count.is_some())
+            && app.config.keymap.lookup(&[chord]) == KeyMatch::Unbound;
+        if counting {
+            let count = app.pending_count.unwrap_or(0) * 10 + digit.to_digit(10).unwrap_or(0) as usize;
+            app.pending_count = Some(count.min(MAX_COUNT));
+            return;
         }
-        (KeyCode::Down, KeyModifiers::NONE) => {
-            app.select_next();
+    }
+    app.pending_keys.push(chord);
+    match app.config.keymap.lookup(&app.pending_keys) {
+        KeyMatch::Action(action) => {
+            app.pending_keys.clear();
+            let count = app.pending_count.take();
+            run_action(action, count, app);
         }
-        (KeyCode::Up, KeyModifiers::NONE) => {
-            app.select_previous();
+        KeyMatch::Pending => {}
+        KeyMatch::Unbound => {
+            // a key that doesn't continue the sequence typed so far starts a new one
+            let restart = app.pending_keys.len() > 1;
+            app.pending_keys.clear();
+            if restart {
+                handle_key_event_view_mode(key, app);
+            } else {
+                app.pending_count = None;
+            }
         }
-        (KeyCode::Char(' '), KeyModifiers::NONE) => {
-            let _ = ToggleTaskStatusCommand.execute(app);
+    }
+}
+
+/// Run `action`, `count` times when it repeats. A count before `gg` or `G`
+/// selects the task at that line
+fn run_action(action: Action, count: Option<usize>, app: &mut AppContext) {
+    match (action, count) {
+        (Action::SelectFirst | Action::SelectLast, Some(line)) => app.select_line(line),
+        // a single confirmation for all the tasks
+        (Action::DeleteTask, Some(count)) if count > 1 => handle_errors(DeleteTasksCommand { count }, app),
+        (action, Some(count)) if action.repeats() => {
+            app.error = None;
+            for _ in 0..count {
+                run_action_once(action, app);
+                // stop at an error, or when the action asks something like deleting subtasks
+                if app.error.is_some() || app.confirmation.is_some() || !matches!(app.input_mode, InputMode::View) {
+                    break;
+                }
+            }
         }
-        (KeyCode::Char('m'), KeyModifiers::NONE) => {
-            let _ = StartEditingExistingTaskCommand.execute(app);
+        (action, _) => run_action_once(action, app),
+    }
+}
+
+/// Run the command of `action`
+fn run_action_once(action: Action, app: &mut AppContext) {
+    match action {
+        Action::AddTask => handle_errors(EnterAddModeCommand, app),
+        Action::AddSubtask => handle_errors(EnterAddSubtaskModeCommand, app),
+        Action::EditTask => handle_errors(StartEditingExistingTaskCommand, app),
+        Action::ToggleStatus => handle_errors(ToggleTaskStatusCommand, app),
+        Action::TogglePriority => handle_errors(ToggleItemPriorityCommand, app),
+        Action::DeleteTask => handle_errors(DeleteTaskCommand, app),
+        Action::SelectNext => app.select_next(),
+        Action::SelectPrevious => app.select_previous(),
+        Action::SelectFirst => app.select_first(),
+        Action::SelectLast => app.select_last(),
+        Action::HalfPageDown => app.select_half_page_down(),
+        Action::HalfPageUp => app.select_half_page_up(),
+        Action::Collapse => handle_errors(CollapseTaskCommand, app),
+        Action::Expand => handle_errors(ExpandTaskCommand, app),
+        Action::SortByPriority => handle_errors(SortByPriorityCommand, app),
+        Action::SortByDate => handle_errors(SortByDateCommand, app),
+        Action::NextProject => handle_errors(NextProjectCommand, app),
+        Action::PreviousProject => handle_errors(PreviousProjectCommand, app),
+        Action::AddProject => handle_errors(EnterAddProjectModeCommand, app),
+        Action::Search => handle_errors(EnterSearchModeCommand, app),
+        Action::Filter => handle_errors(EnterFilterModeCommand, app),
+        Action::PickView => handle_errors(EnterViewPickerCommand, app),
+        Action::Undo => handle_errors(UndoCommand, app),
+        Action::Redo => handle_errors(RedoCommand, app),
+        Action::Export => handle_errors(EnterExportModeCommand, app),
+        Action::Import => handle_errors(EnterImportModeCommand, app),
+        Action::Trash => handle_errors(EnterTrashCommand, app),
+        Action::ArchiveTask => handle_errors(ArchiveTaskCommand, app),
+        Action::Archive => handle_errors(EnterArchiveCommand, app),
+        Action::ToggleCompleted => handle_errors(ToggleCompletedCommand, app),
+        Action::CommandLine => handle_errors(EnterCommandLineCommand, app),
+        Action::ToggleDetails => app.show_details = !app.show_details,
+        Action::ToggleHelp => app.show_help = !app.show_help,
+        Action::Cancel => {
+            if app.show_help {
+                app.show_help = false;
+            } else if !app.input_search.is_empty() {
+                handle_errors(ExitSearchCommand, app);
+            } else if !app.filter.is_all() {
+                handle_errors(ExitFilterCommand, app);
+            }
+            app.error = None;
         }
-        (KeyCode::Char('p'), KeyModifiers::NONE) => {
-            let _ = ToggleItemPriorityCommand.execute(app);
+        Action::Quit => app.quit = true,
+    }
+}
+
+/// Whether `key` saves the task being added or edited: Ctrl + s, or Enter
+/// outside of the description, where it starts a new line
+fn is_save_key(key: KeyEvent, app: &AppContext) -> bool {
+    match key.code {
+        KeyCode::Char('s') => key.modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Enter => !matches!(app.input_field, InputFieldType::Description),
+        _ => false,
+    }
+}
+
+fn handle_key_event_adding_mode(key: KeyEvent, app: &mut AppContext) {
+    match key.code {
+        _ if is_save_key(key, app) => {
+            handle_errors(AddTaskCommand, app);
+            if app.error.is_none() {
+                app.input_mode = InputMode::View;
+            }
+        }
+        KeyCode::Tab => app.next_input_field(),
+        KeyCode::Esc => {
+            let _ = StopEditingCommand.execute(app);
         }
-        (KeyCode::Char('s'), KeyModifiers::NONE) => {
-            app.sort_by_priority();
+        _ => {
+            app.focused_input_mut().handle_key(key);
+        }
+    }
+}
+
+fn handle_key_event_editing_existing_mode(key: KeyEvent, app: &mut AppContext) {
+    match key.code {
+        KeyCode::Tab => app.next_input_field(),
+        _ if is_save_key(key, app) => {
+            handle_errors(FinishEditingExistingTaskCommand, app);
+            if app.error.is_none() {
+                app.input_mode = InputMode::View;
+            }
         }
-        (KeyCode::Char('t'), KeyModifiers::NONE) => {
-            app.sort_by_date();
+        KeyCode::Esc => {
+            let _ = StopEditingCommand.execute(app);
         }
-        (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
-            let _ = DeleteTaskCommand.execute(app);
+        _ => {
+            app.focused_input_mut().handle_key(key);
         }
-        (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-            handle_errors(EnterExportModeCommand, app);
+    }
+}
+
+fn handle_key_event_export_mode(key: KeyEvent, app: &mut AppContext) {
+    match key.code {
+        KeyCode::Esc => ExitExportModeCommand.execute(app).unwrap(),
+        KeyCode::Enter => handle_errors(FinishingExportCommand, app),
+        _ => {
+            app.input_export_path.handle_key(key);
         }
-        _ => {}
     }
 }
 
-fn handle_key_event_adding_mode(key: KeyCode, app: &mut AppContext) {
-    match key {
+fn handle_key_event_import_mode(key: KeyEvent, app: &mut AppContext) {
+    match key.code {
+        KeyCode::Esc => ExitImportModeCommand.execute(app).unwrap(),
         KeyCode::Enter => {
-            handle_errors(AddTaskCommand, app);
+            handle_errors(FinishingImportCommand, app);
             if app.error.is_none() {
                 app.input_mode = InputMode::View;
             }
         }
-        KeyCode::Tab => app.next_input_field(),
-        KeyCode::Char(c) => app.handle_char_input(c),
-        KeyCode::Backspace => app.handle_backspace(),
-        KeyCode::Esc => {
-            let _ = StopEditingCommand.execute(app);
+        _ => {
+            app.input_import_path.handle_key(key);
+        }
+    }
+}
+
+fn handle_key_event_search_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc => handle_errors(ExitSearchCommand, app),
+        KeyCode::Enter => handle_errors(FinishSearchCommand, app),
+        KeyCode::Down => app.select_next(),
+        KeyCode::Up => app.select_previous(),
+        KeyCode::Char(c) => {
+            app.input_search.push(c);
+            handle_errors(UpdateSearchCommand, app);
+        }
+        KeyCode::Backspace => {
+            app.input_search.pop();
+            handle_errors(UpdateSearchCommand, app);
         }
         _ => {}
     }
 }
 
-fn handle_key_event_editing_existing_mode(key: KeyCode, app: &mut AppContext) {
+fn handle_key_event_filter_mode(key: KeyCode, app: &mut AppContext) {
     match key {
-        KeyCode::Tab => app.next_input_field(),
+        KeyCode::Esc => handle_errors(ExitFilterCommand, app),
+        KeyCode::Enter => handle_errors(FinishFilterCommand, app),
+        KeyCode::Char(c) => {
+            app.input_filter.push(c);
+        }
+        KeyCode::Backspace => {
+            app.input_filter.pop();
+        }
+        _ => {}
+    }
+}
+
+fn handle_key_event_view_picker_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc => ExitViewPickerCommand.execute(app).unwrap(),
+        KeyCode::Enter => handle_errors(PickViewCommand, app),
+        KeyCode::Down => app.view_picker.select_next(),
+        KeyCode::Up => app.view_picker.select_previous(),
+        KeyCode::Char('a') => handle_errors(EnterAddViewModeCommand, app),
+        KeyCode::Char('d') => handle_errors(DeleteViewCommand, app),
+        _ => {}
+    }
+}
+
+fn handle_key_event_adding_view_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc => ExitAddViewModeCommand.execute(app).unwrap(),
         KeyCode::Enter => {
-            handle_errors(FinishEditingExistingTaskCommand, app);
+            handle_errors(AddViewCommand, app);
             if app.error.is_none() {
                 app.input_mode = InputMode::View;
             }
         }
-        KeyCode::Char(c) => app.handle_char_input(c),
-        KeyCode::Backspace => app.handle_backspace(),
-        KeyCode::Esc => {
-            let _ = StopEditingCommand.execute(app);
+        KeyCode::Char(c) => {
+            app.input_view_name.push(c);
+        }
+        KeyCode::Backspace => {
+            app.input_view_name.pop();
         }
         _ => {}
     }
 }
 
-fn handle_key_event_export_mode(key: KeyCode, app: &mut AppContext) {
+fn handle_key_event_adding_project_mode(key: KeyCode, app: &mut AppContext) {
     match key {
-        KeyCode::Esc => ExitExportModeCommand.execute(app).unwrap(),
+        KeyCode::Esc => ExitAddProjectModeCommand.execute(app).unwrap(),
         KeyCode::Enter => {
-            handle_errors(FinishingExportCommand, app);
+            handle_errors(AddProjectCommand, app);
             if app.error.is_none() {
                 app.input_mode = InputMode::View;
             }
         }
         KeyCode::Char(c) => {
-            app.input_export_path.push(c);
+            app.input_project_name.push(c);
         }
         KeyCode::Backspace => {
-            app.input_export_path.pop();
+            app.input_project_name.pop();
+        }
+        _ => {}
+    }
+}
+
+fn handle_key_event_delete_subtasks_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Char('c') => handle_errors(DeleteTaskAndSubtasksCommand, app),
+        KeyCode::Char('k') => handle_errors(DeleteTaskKeepingSubtasksCommand, app),
+        KeyCode::Esc => app.input_mode = InputMode::View,
+        _ => {}
+    }
+}
+
+fn handle_key_event_trash_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q' | 'T') => handle_errors(ExitTrashCommand, app),
+        KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
+        KeyCode::Char('r') => handle_errors(RestoreTaskCommand, app),
+        KeyCode::Char('d') | KeyCode::Delete => handle_errors(PurgeTaskCommand, app),
+        KeyCode::Char('E') => handle_errors(EmptyTrashCommand, app),
+        KeyCode::Char('u') => handle_errors(UndoCommand, app),
+        _ => {}
+    }
+}
+
+fn handle_key_event_archive_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q' | 'Z') => handle_errors(ExitArchiveCommand, app),
+        KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
+        KeyCode::Char('r') => handle_errors(UnarchiveTaskCommand, app),
+        KeyCode::Char('d') | KeyCode::Delete => handle_errors(DeleteArchivedTaskCommand, app),
+        KeyCode::Char('u') => handle_errors(UndoCommand, app),
+        _ => {}
+    }
+}
+
+/// Answer the question of the confirmation dialog
+fn handle_key_event_confirmation(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => handle_errors(ConfirmCommand, app),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => handle_errors(CancelConfirmationCommand, app),
+        _ => {}
+    }
+}
+
+fn handle_key_event_command_line_mode(key: KeyCode, app: &mut AppContext) {
+    match key {
+        KeyCode::Esc => handle_errors(ExitCommandLineCommand, app),
+        KeyCode::Enter => handle_errors(RunCommandLineCommand, app),
+        KeyCode::Char(c) => app.input_command.push(c),
+        // like vim, deleting past the start of the command closes the command line
+        KeyCode::Backspace if app.input_command.is_empty() => handle_errors(ExitCommandLineCommand, app),
+        KeyCode::Backspace => {
+            app.input_command.pop();
+        }
+        _ => {}
+    }
+}
+
+/// Insert pasted text in the input being typed, on a single line unless it is
+/// the description
+fn handle_paste(text: &str, app: &mut AppContext) {
+    let line = text.lines().collect::<Vec<_>>().join(" ");
+    match app.input_mode {
+        InputMode::Adding | InputMode::EditingExisting => app.focused_input_mut().insert_str(text),
+        InputMode::Export => app.input_export_path.insert_str(text),
+        InputMode::Import => app.input_import_path.insert_str(text),
+        InputMode::Search => {
+            app.input_search.push_str(&line);
+            handle_errors(UpdateSearchCommand, app);
         }
-        _=>{}
+        InputMode::Filter => app.input_filter.push_str(&line),
+        InputMode::AddingView => app.input_view_name.push_str(&line),
+        InputMode::AddingProject => app.input_project_name.push_str(&line),
+        InputMode::CommandLine => app.input_command.push_str(&line),
+        InputMode::View | InputMode::PickingView | InputMode::DeleteTaskWithSubtasks | InputMode::Trash | InputMode::Archive => {}
     }
 }
 
-fn handle_errors<T: Command>(command:T, app: &mut AppContext) {
-    if let Err(e) = command.execute(app) {
+fn handle_errors<T: Command + 'static>(command:T, app: &mut AppContext) {
+    app.info = None;
+    if let Err(e) = execute_or_confirm(command, app) {
         app.error= Some(e.to_string());
     } else {
         app.error = None;
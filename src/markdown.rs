@@ -0,0 +1,159 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+const HEADING: Style = Style::new().fg(Color::LightBlue).add_modifier(Modifier::BOLD);
+const CODE: Style = Style::new().fg(Color::LightYellow);
+const LINK: Style = Style::new().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED);
+const URL: Style = Style::new().fg(Color::DarkGray);
+const QUOTE: Style = Style::new().fg(Color::Gray).add_modifier(Modifier::ITALIC);
+const CHECKED: Style = Style::new().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
+
+/// Render the basic markdown of a description as styled lines: headings,
+/// bullet and numbered lists, checkboxes, quotes, code blocks, and inline
+/// code, bold, italic and links. Anything else is shown as it is written
+pub fn markdown_lines(text: &str) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    let mut in_code_block = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(Line::from(Span::styled(format!("  {line}"), CODE)));
+            continue;
+        }
+        lines.push(block_line(line));
+    }
+    lines
+}
+
+/// Render a line outside of code blocks
+fn block_line(line: &str) -> Line<'static> {
+    let content = line.trim_start();
+    let indent = " ".repeat(line.len() - content.len());
+    if let Some((level, heading)) = heading(content) {
+        let style = if level == 1 { HEADING.add_modifier(Modifier::UNDERLINED) } else { HEADING };
+        return Line::from(inline_spans(heading, style));
+    }
+    if let Some(quote) = content.strip_prefix('>') {
+        let mut spans = vec![Span::styled("▌ ", QUOTE)];
+        spans.extend(inline_spans(quote.trim_start(), QUOTE));
+        return Line::from(spans);
+    }
+    if is_rule(content) {
+        return Line::from(Span::styled("─".repeat(24), URL));
+    }
+    if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|bullet| content.strip_prefix(bullet)) {
+        let (marker, text, style) = match checkbox(item) {
+            Some((true, text)) => ("☑ ", text, CHECKED),
+            Some((false, text)) => ("☐ ", text, Style::new()),
+            None => ("• ", item, Style::new()),
+        };
+        let mut spans = vec![Span::raw(format!("{indent}{marker}"))];
+        spans.extend(inline_spans(text, style));
+        return Line::from(spans);
+    }
+    if let Some((number, item)) = numbered_item(content) {
+        let mut spans = vec![Span::raw(format!("{indent}{number}. "))];
+        spans.extend(inline_spans(item, Style::new()));
+        return Line::from(spans);
+    }
+    let mut spans = vec![Span::raw(indent)];
+    spans.extend(inline_spans(content, Style::new()));
+    Line::from(spans)
+}
+
+/// Level and text of a `# heading`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    match (level, line[level..].strip_prefix(' ')) {
+        (1..=6, Some(text)) => Some((level, text.trim())),
+        _ => None,
+    }
+}
+
+/// `---`, `***` or `___`
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3 && ["-", "*", "_"].iter().any(|c| line.chars().all(|l| l.to_string() == *c))
+}
+
+/// Whether the item of a list is a checked `[x]` or an unchecked `[ ]` checkbox
+fn checkbox(item: &str) -> Option<(bool, &str)> {
+    let checked = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((checked, item[3..].trim_start()))
+}
+
+/// Number and text of an item like `1. first`
+fn numbered_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let item = line[digits..].strip_prefix(". ")?;
+    (digits > 0).then(|| (&line[..digits], item))
+}
+
+/// Split a line into spans of `style`, with `code`, **bold**, *italic* or _italic_
+/// and [links](url) styled. Markers without a closing one are kept as text
+fn inline_spans(text: &str, style: Style) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let styled = match c {
+            '`' => delimited(rest, "`").map(|(code, after)| (vec![Span::styled(code.to_string(), CODE)], after)),
+            '*' if rest.starts_with("**") => delimited(rest, "**")
+                .map(|(bold, after)| (inline_spans(bold, style.add_modifier(Modifier::BOLD)), after)),
+            '*' | '_' if starts_word(&plain, c) => delimited(rest, &c.to_string())
+                .filter(|(italic, _)| !italic.starts_with(' '))
+                .map(|(italic, after)| (inline_spans(italic, style.add_modifier(Modifier::ITALIC)), after)),
+            '[' => link(rest).map(|(label, url, after)| {
+                let mut link = vec![Span::styled(label.to_string(), LINK)];
+                if label != url {
+                    link.push(Span::styled(format!(" ({url})"), URL));
+                }
+                (link, after)
+            }),
+            _ => None,
+        };
+        match styled {
+            Some((styled, after)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), style));
+                }
+                spans.extend(styled);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(plain, style));
+    }
+    spans
+}
+
+/// Text between `marker` at the start of `text` and the next `marker`, and what follows it
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, &'a str)> {
+    let inner = text.strip_prefix(marker)?;
+    let end = inner.find(marker).filter(|end| *end > 0)?;
+    Some((&inner[..end], &inner[end + marker.len()..]))
+}
+
+/// Label and url of `[label](url)` at the start of `text`, and what follows it
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let (label, rest) = text.strip_prefix('[')?.split_once("](")?;
+    let (url, after) = rest.split_once(')')?;
+    Some((label, url, after))
+}
+
+/// `*` and `_` start italic text only at the start of a word, not inside snake_case
+fn starts_word(before: &str, marker: char) -> bool {
+    marker == '*' || !before.chars().last().is_some_and(char::is_alphanumeric)
+}
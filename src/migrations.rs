@@ -0,0 +1,132 @@
+use crate::error::{Error, Result};
+use rusqlite::Connection;
+
+/// Ordered list of forward migrations. The schema version of a database is the
+/// number of migrations applied to it, stored in `PRAGMA user_version`.
+/// Never edit or reorder an entry once released, append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        description TEXT NOT NULL,
+        completed BOOLEAN NOT NULL,
+        priority INTEGER NOT NULL,
+        end_date DATE
+    );",
+    // 2: tags, many-to-many with tasks
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE task_tags (
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+        PRIMARY KEY (task_id, tag_id)
+    );",
+    // 3: projects, every existing task goes into the default project.
+    // sqlite can't add a REFERENCES column with a non-null default
+    "CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    INSERT INTO projects (id, name) VALUES (1, 'Inbox');
+    ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE;
+    UPDATE tasks SET project_id = 1;",
+    // 4: subtasks
+    "ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE CASCADE;",
+    // 5: recurring tasks, the rule is stored as RRULE text
+    "ALTER TABLE tasks ADD COLUMN recurrence TEXT;",
+    // 6: stable uid (a random uuid v4) and creation/modification timestamps,
+    // used as UID, CREATED and LAST-MODIFIED in the iCalendar export
+    "ALTER TABLE tasks ADD COLUMN uid TEXT;
+    ALTER TABLE tasks ADD COLUMN created_at TEXT;
+    ALTER TABLE tasks ADD COLUMN updated_at TEXT;
+    UPDATE tasks SET
+        uid = lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+            substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', abs(random()) % 4 + 1, 1) ||
+            substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))),
+        created_at = strftime('%Y-%m-%d %H:%M:%S+00:00', 'now'),
+        updated_at = strftime('%Y-%m-%d %H:%M:%S+00:00', 'now');
+    CREATE UNIQUE INDEX tasks_uid ON tasks(uid);",
+    // 7: full-text index on title and description, kept in sync by triggers
+    "CREATE VIRTUAL TABLE tasks_fts USING fts5(
+        title, description, content='tasks', content_rowid='id', tokenize='unicode61 remove_diacritics 2'
+    );
+    CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_fts(rowid, title, description) VALUES (new.id, new.title, new.description);
+    END;
+    CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+        INSERT INTO tasks_fts(tasks_fts, rowid, title, description) VALUES ('delete', old.id, old.title, old.description);
+    END;
+    CREATE TRIGGER tasks_fts_update AFTER UPDATE OF title, description ON tasks BEGIN
+        INSERT INTO tasks_fts(tasks_fts, rowid, title, description) VALUES ('delete', old.id, old.title, old.description);
+        INSERT INTO tasks_fts(rowid, title, description) VALUES (new.id, new.title, new.description);
+    END;
+    INSERT INTO tasks_fts(tasks_fts) VALUES ('rebuild');",
+    // 8: saved views, a filter query with its sort order. The first one shows every task
+    "CREATE TABLE views (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        query TEXT NOT NULL,
+        sort_by TEXT NOT NULL,
+        sort_order TEXT NOT NULL
+    );
+    INSERT INTO views (id, name, query, sort_by, sort_order) VALUES
+        (1, 'All tasks', '', 'priority', 'high'),
+        (2, 'Overdue', 'status:open due<today', 'date', 'low'),
+        (3, 'This week', 'status:open due>=today due<=+7d', 'date', 'low'),
+        (4, 'High priority open', 'priority:high status:open', 'priority', 'high');",
+    // 9: due time, the instant a task is due as ISO-8601 in UTC, NULL for tasks due
+    // on a whole day, and the IANA name of its timezone, NULL for the default one
+    "ALTER TABLE tasks ADD COLUMN due_at TEXT;
+    ALTER TABLE tasks ADD COLUMN due_tz TEXT;",
+    // 10: due dates as yyyy-mm-dd instead of dd-mm-yyyy, so they sort and compare like dates
+    "UPDATE tasks SET end_date = substr(end_date, 7, 4) || '-' || substr(end_date, 4, 2) || '-' || substr(end_date, 1, 2)
+    WHERE end_date GLOB '[0-9][0-9]-[0-9][0-9]-[0-9][0-9][0-9][0-9]';",
+    // 11: trash, deleted tasks are kept with the instant they were deleted until purged
+    "ALTER TABLE tasks ADD COLUMN deleted_at TEXT;
+    CREATE INDEX tasks_deleted_at ON tasks(deleted_at);",
+    // 12: archive, the instant a task was completed, taken from its last change for
+    // the tasks completed before, and the instant it was archived
+    "ALTER TABLE tasks ADD COLUMN completed_at TEXT;
+    ALTER TABLE tasks ADD COLUMN archived_at TEXT;
+    UPDATE tasks SET completed_at = coalesce(updated_at, created_at) WHERE completed = 1;
+    CREATE INDEX tasks_archived_at ON tasks(archived_at);",
+    // 13: view names are unique ignoring case, as they are looked up, views sharing
+    // a name with an older one get their id appended
+    "UPDATE views SET name = name || ' (' || id || ')'
+    WHERE EXISTS (SELECT 1 FROM views AS older WHERE older.name = views.name COLLATE NOCASE AND older.id < views.id);
+    CREATE UNIQUE INDEX views_name_nocase ON views(name COLLATE NOCASE);",
+];
+
+/// Schema version this binary knows how to handle
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Read the schema version of the database behind `conn`
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Bring the database up to `SCHEMA_VERSION` applying, in order, every migration
+/// not applied yet. Each migration runs in its own transaction together with the
+/// version bump, so a failure leaves the database at the last good version.
+/// Fails if the database was created by a newer version of the application.
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(Error::SchemaTooNew {
+            found: current,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as i64 + 1;
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)
+            .map_err(|source| Error::Migration { version, source })?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+/// Id of the project every task belongs to unless moved elsewhere, it can't be deleted
+pub const DEFAULT_PROJECT_ID: i32 = 1;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Project {
+    pub id: i32,
+    pub name: String,
+}
+
+impl Project {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: 0,
+            name: name.trim().to_string(),
+        }
+    }
+
+    /// Name usable as a file name, every character that is not alphanumeric,
+    /// `-` or `_` is replaced by `_`
+    pub fn file_name(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    }
+}
@@ -0,0 +1,293 @@
+use crate::date::today;
+use crate::date_parser::parse_date;
+use crate::task::{Priority, Task};
+use chrono::NaiveDate;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A filter on tasks, parsed from text like
+/// `priority:high due<2026-11-01 status:open tag:infra "foo"`.
+///
+/// Terms next to each other must all match, `or` between terms matches either,
+/// `-` or `not` in front of a term negates it and parentheses group terms.
+/// Supported terms:
+/// - `priority:high|medium|low`, `priority>=medium` for medium and high
+/// - `status:open|done`
+/// - `tag:name` or `#name`
+/// - `project:name`, quote names with spaces: `project:"side projects"`
+/// - `due:none`, `due:any`, `due:DATE`, `due<DATE`, `due<=DATE`, `due>DATE`, `due>=DATE`
+///   where DATE is any date of `date_parser::parse_date`, e.g. `yyyy-mm-dd`, `today`,
+///   `+7d`, `eom` or, quoted, `"next fri"`
+/// - `repeat:yes|no`
+/// - any other word, or quoted text, is searched in titles and descriptions
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Query {
+    /// Every query must match, matches every task when empty
+    And(Vec<Query>),
+    /// At least one query must match
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+/// Comparison of a term, for dates earlier is less, for priorities lower is less
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Term {
+    Priority(Comparison, Priority),
+    Completed(bool),
+    Tag(String),
+    /// Project name, case insensitive
+    Project(String),
+    /// Project by id, not part of the text syntax: used to limit a query to the shown project
+    ProjectId(i32),
+    Due(Comparison, NaiveDate),
+    HasDue(bool),
+    Recurring(bool),
+    /// Words searched in title and description
+    Text(String),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseQueryError(pub String);
+
+impl Display for ParseQueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid filter: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseQueryError {}
+
+impl Default for Query {
+    fn default() -> Self {
+        Query::all()
+    }
+}
+
+impl Query {
+    /// Query matching every task
+    pub fn all() -> Self {
+        Query::And(vec![])
+    }
+
+    pub fn is_all(&self) -> bool {
+        matches!(self, Query::And(queries) if queries.is_empty())
+    }
+
+    /// Query matching the tasks of a project that match this query too
+    pub fn in_project(&self, project_id: i32) -> Self {
+        Query::And(vec![Query::Term(Term::ProjectId(project_id)), self.clone()])
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    /// A bare word, quoted parts inside it are unquoted: `project:"a b"` is `project:a b`
+    Word(String),
+    /// Text entirely between quotes
+    Quoted(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseQueryError> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let quoted = c == '"';
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c != '"' {
+                        word.push(c);
+                        continue;
+                    }
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => word.push(c),
+                            None => return Err(ParseQueryError("missing closing quote".to_string())),
+                        }
+                    }
+                }
+                tokens.push(if quoted { Token::Quoted(word) } else { Token::Word(word) });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseQueryError> {
+        let mut queries = vec![self.parse_and()?];
+        while self.is_keyword("or") {
+            self.next();
+            queries.push(self.parse_and()?);
+        }
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => Query::Or(queries),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseQueryError> {
+        let mut queries = vec![];
+        while let Some(token) = self.peek() {
+            if *token == Token::Close || self.is_keyword("or") {
+                break;
+            }
+            queries.push(self.parse_unary()?);
+        }
+        match queries.len() {
+            0 => Err(ParseQueryError("expected a filter term".to_string())),
+            1 => Ok(queries.remove(0)),
+            _ => Ok(Query::And(queries)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseQueryError> {
+        if self.is_keyword("not") {
+            self.next();
+            return Ok(Query::Not(Box::new(self.parse_unary()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(ParseQueryError("missing closing parenthesis".to_string())),
+                }
+            }
+            Some(Token::Close) => Err(ParseQueryError("unexpected closing parenthesis".to_string())),
+            Some(Token::Quoted(text)) => Ok(Query::Term(Term::Text(text))),
+            Some(Token::Word(word)) => match word.strip_prefix('-') {
+                Some(negated) if !negated.is_empty() => {
+                    Ok(Query::Not(Box::new(Query::Term(parse_term(negated)?))))
+                }
+                _ => Ok(Query::Term(parse_term(&word)?)),
+            },
+            None => Err(ParseQueryError("expected a filter term".to_string())),
+        }
+    }
+}
+
+fn parse_term(word: &str) -> Result<Term, ParseQueryError> {
+    let err = |message: &str| ParseQueryError(format!("{message} in {word:?}"));
+    if let Some(tag) = word.strip_prefix('#') {
+        return tag_term(tag).ok_or_else(|| err("empty tag"));
+    }
+    let lowercase = word.to_lowercase();
+    if let Some((comparison, value)) = lowercase.strip_prefix("due").and_then(split_comparison) {
+        return match (comparison, value) {
+            (Comparison::Equal, "none") => Ok(Term::HasDue(false)),
+            (Comparison::Equal, "any") => Ok(Term::HasDue(true)),
+            _ => {
+                let date = parse_date(value, today()).ok_or_else(|| err("invalid date"))?;
+                Ok(Term::Due(comparison, date))
+            }
+        };
+    }
+    if let Some((comparison, value)) = lowercase.strip_prefix("priority").and_then(split_comparison) {
+        return Priority::parse(value)
+            .map(|priority| Term::Priority(comparison, priority))
+            .ok_or_else(|| err("unknown priority, use high, medium or low"));
+    }
+    let Some((key, value)) = word.split_once(':') else {
+        return Ok(Term::Text(word.to_string()));
+    };
+    match key.to_lowercase().as_str() {
+        "status" => match value.to_lowercase().as_str() {
+            "open" | "todo" => Ok(Term::Completed(false)),
+            "done" | "completed" => Ok(Term::Completed(true)),
+            _ => Err(err("unknown status, use open or done")),
+        },
+        "tag" => tag_term(value).ok_or_else(|| err("empty tag")),
+        "project" if !value.trim().is_empty() => Ok(Term::Project(value.trim().to_string())),
+        "repeat" => match value.to_lowercase().as_str() {
+            "yes" | "true" => Ok(Term::Recurring(true)),
+            "no" | "false" => Ok(Term::Recurring(false)),
+            _ => Err(err("use repeat:yes or repeat:no")),
+        },
+        _ => Err(err("unknown filter")),
+    }
+}
+
+/// Split the rest of a term after its key in comparison and value, `:` is `Equal`
+fn split_comparison(rest: &str) -> Option<(Comparison, &str)> {
+    [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        (":", Comparison::Equal),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(operator, comparison)| rest.strip_prefix(operator).map(|value| (comparison, value)))
+}
+
+fn tag_term(value: &str) -> Option<Term> {
+    Task::parse_tags(value).into_iter().next().map(Term::Tag)
+}
+
+/// Parse a filter, an empty or blank text is a query matching every task
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Query::all());
+        }
+        let mut parser = Parser { tokens, position: 0 };
+        let query = parser.parse_or()?;
+        if parser.position < parser.tokens.len() {
+            return Err(ParseQueryError("unexpected closing parenthesis".to_string()));
+        }
+        Ok(query)
+    }
+}
@@ -0,0 +1,284 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How a task repeats. Calendar based rules follow the iCalendar RRULE semantic
+/// [rfc5545](https://datatracker.ietf.org/doc/html/rfc5545#section-3.3.10),
+/// monthly rules skip the months without their day as BYMONTHDAY does.
+/// `AfterCompletion` schedules the next occurrence counting from the day the
+/// previous one has been completed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Recurrence {
+    Daily { interval: u32 },
+    Weekly { interval: u32, weekdays: Vec<Weekday> },
+    Monthly { interval: u32, day: u32 },
+    AfterCompletion { days: u32 },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseRecurrenceError(pub String);
+
+impl Display for ParseRecurrenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid repeat rule {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseRecurrenceError {}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Two letters weekday code used by RRULE BYDAY
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Parse a weekday from its RRULE code, its english name or any prefix of
+/// at least two letters of it, e.g. `mo`, `mon`, `monday`
+pub(crate) fn parse_weekday(value: &str) -> Option<Weekday> {
+    let value = value.to_lowercase();
+    if value.len() < 2 {
+        return None;
+    }
+    WEEKDAYS.into_iter().find(|day| {
+        let name = match day {
+            Weekday::Mon => "monday",
+            Weekday::Tue => "tuesday",
+            Weekday::Wed => "wednesday",
+            Weekday::Thu => "thursday",
+            Weekday::Fri => "friday",
+            Weekday::Sat => "saturday",
+            Weekday::Sun => "sunday",
+        };
+        name.starts_with(&value)
+    })
+}
+
+impl Recurrence {
+    /// RRULE value for the calendar based rules, `AfterCompletion` can't be
+    /// expressed as a RRULE and returns None
+    pub fn rrule(&self) -> Option<String> {
+        match self {
+            Recurrence::Daily { interval } => Some(format!("FREQ=DAILY;INTERVAL={interval}")),
+            Recurrence::Weekly { interval, weekdays } if weekdays.is_empty() => {
+                Some(format!("FREQ=WEEKLY;INTERVAL={interval}"))
+            }
+            Recurrence::Weekly { interval, weekdays } => Some(format!(
+                "FREQ=WEEKLY;INTERVAL={interval};BYDAY={}",
+                weekdays
+                    .iter()
+                    .map(|d| weekday_code(*d))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+            Recurrence::Monthly { interval, day } => {
+                Some(format!("FREQ=MONTHLY;INTERVAL={interval};BYMONTHDAY={day}"))
+            }
+            Recurrence::AfterCompletion { .. } => None,
+        }
+    }
+
+    /// Parse the subset of RRULE produced by `Recurrence::rrule`, plus the
+    /// non-standard `X-AFTER-COMPLETION` used to store `AfterCompletion`
+    pub fn from_rrule(value: &str) -> Result<Self, ParseRecurrenceError> {
+        let err = || ParseRecurrenceError(value.to_string());
+        let mut freq = None;
+        let mut interval = 1;
+        let mut weekdays = vec![];
+        let mut month_day = None;
+        let mut after_completion = false;
+        for part in value.trim().trim_start_matches("RRULE:").split(';') {
+            let (key, val) = part.split_once('=').ok_or_else(err)?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => freq = Some(val.to_uppercase()),
+                "INTERVAL" => interval = val.parse().map_err(|_| err())?,
+                "BYDAY" => {
+                    weekdays = val
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(err)?
+                }
+                "BYMONTHDAY" => month_day = Some(val.parse().map_err(|_| err())?),
+                "X-AFTER-COMPLETION" => after_completion = val.eq_ignore_ascii_case("TRUE"),
+                _ => return Err(err()),
+            }
+        }
+        if interval == 0 {
+            return Err(err());
+        }
+        let recurrence = match (freq.as_deref(), after_completion) {
+            (Some("DAILY"), true) => Recurrence::AfterCompletion { days: interval },
+            (Some("DAILY"), false) => Recurrence::Daily { interval },
+            (Some("WEEKLY"), false) => Recurrence::Weekly { interval, weekdays },
+            (Some("MONTHLY"), false) => Recurrence::Monthly {
+                interval,
+                day: month_day.ok_or_else(err)?,
+            },
+            _ => return Err(err()),
+        };
+        recurrence.validate().ok_or_else(err)
+    }
+
+    /// Value stored in the database
+    pub fn to_db_string(&self) -> String {
+        match self {
+            Recurrence::AfterCompletion { days } => {
+                format!("FREQ=DAILY;INTERVAL={days};X-AFTER-COMPLETION=TRUE")
+            }
+            _ => self.rrule().unwrap_or_default(),
+        }
+    }
+
+    fn validate(self) -> Option<Self> {
+        match &self {
+            Recurrence::Monthly { day, .. } if !(1..=31).contains(day) => None,
+            _ => Some(self),
+        }
+    }
+
+    /// Date of the occurrence following the one due on `due` and completed on `completed_on`.
+    /// Calendar based rules without a due date count from `completed_on`. None for monthly
+    /// rules whose months never have their day, like `every 12 months 30` from February
+    pub fn next_date(&self, due: Option<NaiveDate>, completed_on: NaiveDate) -> Option<NaiveDate> {
+        let base = due.unwrap_or(completed_on);
+        match self {
+            Recurrence::Daily { interval } => Some(base + Days::new(*interval as u64)),
+            Recurrence::Weekly { interval, weekdays } if weekdays.is_empty() => {
+                Some(base + Days::new(7 * *interval as u64))
+            }
+            Recurrence::Weekly { interval, weekdays } => {
+                let week_start = base - Days::new(base.weekday().num_days_from_monday() as u64);
+                let mut date = base;
+                loop {
+                    date = date + Days::new(1);
+                    let week = (date - week_start).num_days() / 7;
+                    if week % *interval as i64 == 0 && weekdays.contains(&date.weekday()) {
+                        return Some(date);
+                    }
+                }
+            }
+            Recurrence::Monthly { interval, day } => {
+                let month_start = base.with_day(1).unwrap_or(base);
+                // the months of the rule go through every month of the year within 12 steps
+                (0..=12)
+                    .map(|n| month_start + Months::new(n * interval))
+                    .filter_map(|month| month.with_day(*day))
+                    .find(|date| *date > base)
+            }
+            Recurrence::AfterCompletion { days } => Some(completed_on + Days::new(*days as u64)),
+        }
+    }
+}
+
+/// Parse a rule written by the user:
+/// `daily`, `weekly`, `monthly`, `every N days|weeks|months`, optionally followed by
+/// weekdays for weekly rules (`weekly mon,fri`) or a day for monthly rules
+/// (`monthly 15`), `after N days` to repeat counting from completion.
+/// RRULE values are accepted too.
+impl FromStr for Recurrence {
+    type Err = ParseRecurrenceError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.to_uppercase().contains("FREQ=") {
+            return Self::from_rrule(value);
+        }
+        let err = || ParseRecurrenceError(value.to_string());
+        let lowercase = value.to_lowercase();
+        let words: Vec<&str> = lowercase
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|w| !w.is_empty())
+            .collect();
+        let (interval, unit, rest) = match words.as_slice() {
+            ["after", n, unit] if unit.starts_with("day") => {
+                let days = n.parse().map_err(|_| err())?;
+                if days == 0 {
+                    return Err(err());
+                }
+                return Ok(Recurrence::AfterCompletion { days });
+            }
+            ["daily", rest @ ..] => (1, "day", rest),
+            ["weekly", rest @ ..] => (1, "week", rest),
+            ["monthly", rest @ ..] => (1, "month", rest),
+            ["every", n, unit, rest @ ..] if n.parse::<u32>().is_ok() => {
+                (n.parse().map_err(|_| err())?, unit.trim_end_matches('s'), rest)
+            }
+            ["every", unit @ ("day" | "week" | "month"), rest @ ..] => (1, *unit, rest),
+            // `every mon fri`
+            ["every", rest @ ..] => (1, "week", rest),
+            _ => return Err(err()),
+        };
+        if interval == 0 {
+            return Err(err());
+        }
+        let recurrence = match (unit, rest) {
+            ("day", []) => Recurrence::Daily { interval },
+            ("week", days) => Recurrence::Weekly {
+                interval,
+                weekdays: {
+                    let mut weekdays = days
+                        .iter()
+                        .map(|d| parse_weekday(d))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(err)?;
+                    weekdays.sort_by_key(|d| d.num_days_from_monday());
+                    weekdays.dedup();
+                    weekdays
+                },
+            },
+            ("month", [day]) => Recurrence::Monthly {
+                interval,
+                day: day
+                    .trim_end_matches(|c: char| c.is_alphabetic())
+                    .parse()
+                    .map_err(|_| err())?,
+            },
+            _ => return Err(err()),
+        };
+        recurrence.validate().ok_or_else(err)
+    }
+}
+
+/// Format the rule in the same syntax accepted by `from_str`
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let days = |weekdays: &Vec<Weekday>| {
+            weekdays
+                .iter()
+                .map(|d| d.to_string().to_lowercase())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self {
+            Recurrence::Daily { interval: 1 } => write!(f, "daily"),
+            Recurrence::Daily { interval } => write!(f, "every {interval} days"),
+            Recurrence::Weekly { interval: 1, weekdays } if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly { interval: 1, weekdays } => write!(f, "weekly {}", days(weekdays)),
+            Recurrence::Weekly { interval, weekdays } if weekdays.is_empty() => {
+                write!(f, "every {interval} weeks")
+            }
+            Recurrence::Weekly { interval, weekdays } => {
+                write!(f, "every {interval} weeks {}", days(weekdays))
+            }
+            Recurrence::Monthly { interval: 1, day } => write!(f, "monthly {day}"),
+            Recurrence::Monthly { interval, day } => write!(f, "every {interval} months {day}"),
+            Recurrence::AfterCompletion { days } => write!(f, "after {days} days"),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::task::Task;
+
+/// Markers put around the matched terms in `SearchHit::title` and `SearchHit::snippet`
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// A task matching a full-text search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub task: Task,
+    /// bm25 rank, lower is a better match
+    pub rank: f64,
+    /// Title with the matched terms between `MATCH_START` and `MATCH_END`
+    pub title: String,
+    /// Part of the description around the matched terms, marked like `title`
+    pub snippet: String,
+}
+
+/// Turn the text typed by the user into an FTS5 query matching the tasks that
+/// contain a word starting with each of the typed words, so results show up
+/// while typing. Returns None if there is nothing to search
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        // quoting makes FTS5 operators and punctuation plain text
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(terms.join(" "))
+}
+
+/// Split a highlighted text in parts, each paired with whether it is a match
+pub fn highlighted_parts(text: &str) -> Vec<(&str, bool)> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(MATCH_START) {
+        if start > 0 {
+            parts.push((&rest[..start], false));
+        }
+        rest = &rest[start + MATCH_START.len_utf8()..];
+        let end = rest.find(MATCH_END).unwrap_or(rest.len());
+        parts.push((&rest[..end], true));
+        rest = rest.get(end + MATCH_END.len_utf8()..).unwrap_or_default();
+    }
+    if !rest.is_empty() {
+        parts.push((rest, false));
+    }
+    parts
+}
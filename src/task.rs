@@ -1,7 +1,14 @@
-use crate::date::TaskDate;
+use crate::date::{default_timezone, Due, TaskDate};
+use crate::project::DEFAULT_PROJECT_ID;
+use crate::recurrence::Recurrence;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     Low = 3 ,
     Medium = 2,
@@ -26,6 +33,25 @@ impl Priority {
         }
     }
 
+    /// Parse a priority given by name, first letter or number, e.g. `high`, `h` or `1`
+    pub fn parse(value: &str) -> Option<Priority> {
+        match value.to_lowercase().as_str() {
+            "high" | "h" | "1" => Some(Priority::High),
+            "medium" | "m" | "2" => Some(Priority::Medium),
+            "low" | "l" | "3" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    /// Lower case name, as accepted by `parse`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
     pub fn to_u8(&self) -> u8 {
         match self {
             Priority::Low => 3,
@@ -52,7 +78,26 @@ pub struct Task {
     pub description: String,
     pub completed: bool,
     pub priority: Priority,
+    /// Day the task is due, in `timezone` when the task is due at a time
     pub date: TaskDate,
+    /// Time of `date` the task is due at, None when it is due on the whole day
+    pub due_time: Option<NaiveTime>,
+    /// Timezone of `due_time`, None for `default_timezone()`
+    pub timezone: Option<Tz>,
+    pub tags: Vec<String>,
+    pub project_id: i32,
+    pub parent_id: Option<i32>,
+    pub recurrence: Option<Recurrence>,
+    /// Globally unique id, kept across exports. Empty until the task is stored
+    pub uid: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// When the task was moved to the trash, None for the tasks in the list
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// When the task was completed, None for the open tasks
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the task was moved to the archive, None for the tasks in the list
+    pub archived_at: Option<DateTime<Utc>>,
 }
 
 impl Default for Task {
@@ -73,6 +118,136 @@ impl Task {
             completed: false,
             priority: Priority::Low,
             date: TaskDate(None),
+            due_time: None,
+            timezone: None,
+            tags: vec![],
+            project_id: DEFAULT_PROJECT_ID,
+            parent_id: None,
+            recurrence: None,
+            uid: String::new(),
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            completed_at: None,
+            archived_at: None,
         }
     }
+
+    /// Next occurrence of a recurring task completed on `completed_on`,
+    /// None if the task doesn't repeat or its rule has no next date
+    pub fn next_occurrence(&self, completed_on: NaiveDate) -> Option<Task> {
+        let recurrence = self.recurrence.as_ref()?;
+        Some(Task {
+            id: 0,
+            completed: false,
+            date: TaskDate(Some(recurrence.next_date(self.date.0, completed_on)?)),
+            uid: String::new(),
+            created_at: None,
+            updated_at: None,
+            ..self.clone()
+        })
+    }
+
+    /// When the task is due, None if it has no due date
+    pub fn due(&self) -> Option<Due> {
+        Some(Due {
+            date: self.date.0?,
+            time: self.due_time,
+            timezone: self.timezone,
+        })
+    }
+
+    pub fn set_due(&mut self, due: Option<Due>) {
+        self.date = TaskDate(due.map(|d| d.date));
+        self.due_time = due.and_then(|d| d.time);
+        self.timezone = due.and_then(|d| d.timezone).filter(|_| self.due_time.is_some());
+    }
+
+    /// Instant the task is due at, None if it has no due date or is due on the whole day
+    pub fn due_at(&self) -> Option<DateTime<Tz>> {
+        let date_time = self.date.0?.and_time(self.due_time?);
+        let timezone = self.timezone.unwrap_or_else(default_timezone);
+        // a time skipped by a DST change is moved forward by the skipped hour
+        timezone
+            .from_local_datetime(&date_time)
+            .earliest()
+            .or_else(|| timezone.from_local_datetime(&(date_time + Duration::hours(1))).earliest())
+    }
+
+    /// Day and time the task is due in `default_timezone()`, as displayed.
+    /// Tasks due on a whole day are due on the same day everywhere
+    pub fn local_due(&self) -> Option<(NaiveDate, Option<NaiveTime>)> {
+        match self.due_at() {
+            Some(due_at) => {
+                let local = due_at.with_timezone(&default_timezone());
+                Some((local.date_naive(), Some(local.time())))
+            }
+            None => self.date.0.map(|date| (date, None)),
+        }
+    }
+
+    /// Parse a list of tags separated by spaces or commas, a leading `#` is
+    /// dropped. Returns the tags sorted and without duplicates.
+    pub fn parse_tags(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(|tag| tag.trim_start_matches('#').to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
+
+/// A task together with its subtasks
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TaskNode {
+    pub task: Task,
+    pub children: Vec<TaskNode>,
+}
+
+impl TaskNode {
+    /// Arrange `tasks` in trees following `parent_id`. Siblings keep the relative
+    /// order they have in `tasks`, tasks whose parent is not in `tasks` become roots.
+    pub fn build_forest(tasks: Vec<Task>) -> Vec<TaskNode> {
+        let ids: HashSet<i32> = tasks.iter().map(|t| t.id).collect();
+        let mut roots = vec![];
+        let mut children: HashMap<i32, Vec<Task>> = HashMap::new();
+        for task in tasks {
+            match task.parent_id {
+                Some(parent_id) if ids.contains(&parent_id) && parent_id != task.id => {
+                    children.entry(parent_id).or_default().push(task)
+                }
+                _ => roots.push(task),
+            }
+        }
+        roots
+            .into_iter()
+            .map(|task| Self::build_node(task, &mut children))
+            .collect()
+    }
+
+    fn build_node(task: Task, children: &mut HashMap<i32, Vec<Task>>) -> TaskNode {
+        // removing the entry also stops recursion on corrupted, cyclic, parent links
+        let subtasks = children.remove(&task.id).unwrap_or_default();
+        TaskNode {
+            task,
+            children: subtasks
+                .into_iter()
+                .map(|t| Self::build_node(t, children))
+                .collect(),
+        }
+    }
+
+    /// Number of completed subtasks and total number of subtasks, at any depth
+    pub fn progress(&self) -> (usize, usize) {
+        self.children.iter().fold((0, 0), |(done, total), child| {
+            let (child_done, child_total) = child.progress();
+            (
+                done + child_done + child.task.completed as usize,
+                total + child_total + 1,
+            )
+        })
+    }
 }
@@ -1,9 +1,33 @@
-use crate::date::{TaskDate, DATE_FORMAT};
+use crate::date::{default_timezone, TaskDate, ISO_DATE_FORMAT};
+use crate::error::{Error, Result};
+use crate::migrations;
+use crate::project::{Project, DEFAULT_PROJECT_ID};
+use crate::recurrence::Recurrence;
+use crate::query::{Comparison, Query, Term};
+use crate::search::{fts_query, SearchHit, MATCH_END, MATCH_START};
 use crate::task::{Priority, Task};
-use anyhow::{Context, Result};
-use chrono::NaiveDate;
-use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
-use rusqlite::{params, Connection, Row, ToSql};
+use crate::task_manager::{SortBy, SortOrder};
+use crate::view::{View, DEFAULT_VIEW_ID};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Type, Value, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Params, Row, ToSql};
+
+/// Columns read by `Task::try_from(&Row)`, in order. Tags are collected in a
+/// single comma separated column.
+const TASK_COLUMNS: &str = "id, title, description, completed, priority, end_date,
+    (SELECT group_concat(tags.name, ',') FROM task_tags JOIN tags ON tags.id = task_tags.tag_id
+     WHERE task_tags.task_id = tasks.id) AS tags, project_id, parent_id, recurrence, uid, created_at, updated_at,
+    due_at, due_tz, deleted_at, completed_at, archived_at";
+
+/// Ids of a task and of all its subtasks at any depth, as the `subtree(task_id)`
+/// table, for the task `?1`
+const SUBTREE: &str = "WITH RECURSIVE subtree(task_id, depth) AS (
+        SELECT ?1, 0
+        UNION ALL
+        SELECT tasks.id, subtree.depth + 1 FROM tasks JOIN subtree ON tasks.parent_id = subtree.task_id
+        WHERE subtree.depth < 100
+    )";
 
 #[derive(Debug)]
 pub struct DB {
@@ -13,195 +37,722 @@ pub struct DB {
 impl DB {
     /// Create and return a connection to a database located at path
     /// if path is an empty string creates and in memory db instance
-    pub fn create_and_return_connection(path: &str) -> DB {
+    pub fn create_and_return_connection(path: &str) -> Result<DB> {
         let conn: Connection = if path.is_empty() {
-            Connection::open_in_memory()
-                .context("Can't open in-memory DB.")
-                .unwrap()
+            Connection::open_in_memory()?
         } else {
-            Connection::open(path)
-                .context("Can't open database")
-                .unwrap()
+            Connection::open(path)?
         };
+        conn.pragma_update(None, "foreign_keys", true)?;
         let mut db = DB { connection: conn };
-        db.init();
-        db
-    }
-
-    fn init(&mut self) {
-        self.connection
-            .execute(
-                "CREATE TABLE IF NOT EXISTS tasks (
-            id INTEGER PRIMARY KEY,
-            title TEXT NOT NULL,
-            description TEXT NOT NULL,
-            completed BOOLEAN NOT NULL,
-            priority INTEGER NOT NULL,
-            end_date DATE
-        )",
-                [],
-            )
-            .context("Can't create the DB")
-            .unwrap();
-    }
-
-    pub fn insert_task(&self, task: &Task) {
-        self.connection
-            .execute(
-                "INSERT INTO tasks (title, description, completed, priority, end_date) VALUES (?1, ?2, 0, ?3, ?4)",
-                params![
-                    task.title.trim(),
-                    task.description.trim(),
-                    task.priority.to_u8(),
-                    task.date,
-                ],
-            )
-            .context("Can't add task to DB.")
-            .unwrap();
+        db.init()?;
+        Ok(db)
+    }
+
+    /// Apply the pending schema migrations, refuses to go on if the database
+    /// was created by a newer version of the application
+    fn init(&mut self) -> Result<()> {
+        migrations::migrate(&mut self.connection)
     }
 
-    pub fn get_all_tasks(&self) -> Vec<Task> {
+    /// Run a query selecting `TASK_COLUMNS` and collect the resulting tasks
+    fn query_tasks<P: Params>(&self, sql: &str, params: P) -> Result<Vec<Task>> {
+        let mut stmt = self.connection.prepare(sql)?;
+        let tasks = stmt
+            .query_map(params, |row| Task::try_from(row))?
+            .collect::<rusqlite::Result<Vec<Task>>>()?;
+        Ok(tasks)
+    }
+
+    pub fn insert_task(&self, task: &Task) -> Result<i32> {
+        let tx = self.connection.unchecked_transaction()?;
+        let task_id = Self::insert_task_with(&tx, task)?;
+        tx.commit()?;
+        Ok(task_id)
+    }
+
+    /// Store several tasks in a single transaction keeping their completed status.
+    /// A task whose uid is already in the database updates the existing one, its
+    /// project and parent are left untouched, and brings it back from the trash.
+    /// A task imported as open leaves the archive. Returns the ids of the tasks in the same order
+    pub fn upsert_tasks_by_uid(&self, tasks: &[Task]) -> Result<Vec<i32>> {
+        let tx = self.connection.unchecked_transaction()?;
+        let mut ids = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let existing: Option<i32> = tx
+                .query_row("SELECT id FROM tasks WHERE uid = ?1", params![task.uid], |row| row.get(0))
+                .optional()?;
+            let task_id = match existing {
+                Some(task_id) => {
+                    tx.execute(
+                        "UPDATE tasks SET title = ?2, description = ?3, priority = ?4, end_date = ?5, recurrence = ?6, updated_at = ?7,
+                            due_at = ?8, due_tz = ?9, deleted_at = NULL
+                         WHERE id = ?1",
+                        params![task_id, task.title.trim(), task.description.trim(), task.priority.to_u8(), task.date, task.recurrence, Utc::now(), due_at_column(task), due_tz_column(task)],
+                    )?;
+                    Self::set_task_tags(&tx, task_id, &task.tags)?;
+                    // a parent left in the trash would take the task with it when purged
+                    Self::detach_from_parent_with(&tx, task_id, "deleted_at")?;
+                    task_id
+                }
+                None => Self::insert_task_with(&tx, task)?,
+            };
+            tx.execute(
+                "UPDATE tasks SET completed = ?2, completed_at = CASE WHEN ?2 THEN coalesce(?3, completed_at, ?4) END,
+                    archived_at = CASE WHEN ?2 THEN archived_at END
+                 WHERE id = ?1",
+                params![task_id, task.completed, task.completed_at, Utc::now()],
+            )?;
+            if !task.completed {
+                Self::detach_from_parent_with(&tx, task_id, "archived_at")?;
+            }
+            ids.push(task_id);
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    /// Get the task with `uid`, in the trash too
+    pub fn get_task_by_uid(&self, uid: &str) -> Result<Option<Task>> {
         let mut stmt = self
             .connection
-            .prepare("SELECT id, title, description, completed, priority, end_date FROM tasks")
-            .unwrap();
-        let task_row_iter = stmt
-            .query_map([], |row| Task::try_from(row))
-            .context("Can't get results from DB.")
-            .unwrap();
-        let mut tasks = Vec::new();
-        for task in task_row_iter {
-            tasks.push(task.unwrap());
+            .prepare(&format!("SELECT {TASK_COLUMNS} FROM tasks where uid = ?1"))?;
+        Ok(stmt
+            .query_row(params![uid], |row| Task::try_from(row))
+            .optional()?)
+    }
+
+    fn insert_task_with(conn: &Connection, task: &Task) -> Result<i32> {
+        conn.execute(
+            "INSERT INTO tasks (title, description, completed, priority, end_date, project_id, parent_id, recurrence, uid, created_at, updated_at, due_at, due_tz)
+             VALUES (?1, ?2, 0, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                task.title.trim(),
+                task.description.trim(),
+                task.priority.to_u8(),
+                task.date,
+                task.project_id,
+                task.parent_id,
+                task.recurrence,
+                new_uid_if_empty(&task.uid),
+                task.created_at.unwrap_or_else(Utc::now),
+                Utc::now(),
+                due_at_column(task),
+                due_tz_column(task),
+            ],
+        )?;
+        let task_id = conn.last_insert_rowid() as i32;
+        Self::set_task_tags(conn, task_id, &task.tags)?;
+        Ok(task_id)
+    }
+
+    /// Write `task` with its id and completed state, inserting it if it doesn't exist.
+    /// Used to bring back a previous state of a task
+    pub fn restore_task(&self, task: &Task) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let restored = tx.execute(
+            "INSERT INTO tasks (id, title, description, completed, priority, end_date, project_id, parent_id, recurrence, uid, created_at, updated_at, due_at, due_tz, deleted_at,
+                completed_at, archived_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+             ON CONFLICT(id) DO UPDATE SET title = excluded.title, description = excluded.description,
+                completed = excluded.completed, priority = excluded.priority, end_date = excluded.end_date,
+                project_id = excluded.project_id, parent_id = excluded.parent_id, recurrence = excluded.recurrence,
+                uid = excluded.uid, created_at = excluded.created_at, updated_at = excluded.updated_at,
+                due_at = excluded.due_at, due_tz = excluded.due_tz, deleted_at = excluded.deleted_at,
+                completed_at = excluded.completed_at, archived_at = excluded.archived_at",
+            params![
+                task.id,
+                task.title,
+                task.description,
+                task.completed,
+                task.priority.to_u8(),
+                task.date,
+                task.project_id,
+                task.parent_id,
+                task.recurrence,
+                new_uid_if_empty(&task.uid),
+                task.created_at.unwrap_or_else(Utc::now),
+                // the task changed again, calendars must pick up the restored version
+                Utc::now(),
+                due_at_column(task),
+                due_tz_column(task),
+                task.deleted_at,
+                task.completed_at,
+                task.archived_at,
+            ],
+        )?;
+        Self::set_task_tags(&tx, task.id, &task.tags)?;
+        tx.commit()?;
+        Ok(restored)
+    }
+
+    /// Get a task followed by all its subtasks at any depth, parents come before their subtasks
+    pub fn get_task_with_subtasks(&self, task_id: i32) -> Result<Vec<Task>> {
+        self.query_tasks(
+            &format!(
+                "{SUBTREE}
+                SELECT {TASK_COLUMNS} FROM tasks JOIN subtree ON tasks.id = subtree.task_id
+                WHERE tasks.deleted_at IS NULL
+                order by subtree.depth asc, tasks.id asc"
+            ),
+            params![task_id],
+        )
+    }
+
+    /// Complete a recurring task: the task stops repeating and `next` is added
+    /// as its next occurrence. Returns the id of the new occurrence
+    pub fn complete_and_reschedule(&self, task_id: i32, next: &Task) -> Result<i32> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE tasks SET completed = 1, completed_at = ?2, recurrence = NULL, updated_at = ?2 WHERE id = ?1",
+            params![task_id, Utc::now()],
+        )?;
+        let next_id = Self::insert_task_with(&tx, next)?;
+        tx.commit()?;
+        Ok(next_id)
+    }
+
+    /// Replace the tags of a task, tags not used anymore by any task are removed
+    fn set_task_tags(conn: &Connection, task_id: i32, tags: &[String]) -> Result<()> {
+        conn.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task_id])?;
+        for tag in tags {
+            conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+                params![task_id, tag],
+            )?;
         }
-        tasks
+        conn.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags)",
+            [],
+        )?;
+        Ok(())
     }
 
-    pub fn get_task_by_id(&self, task_id: i32) -> Result<Task> {
+    /// Tags of the tasks that are not in the trash
+    pub fn get_all_tags(&self) -> Result<Vec<String>> {
         let mut stmt = self.connection.prepare(
-            "SELECT id, title, description, completed, priority, end_date FROM tasks where id = ?1",
+            "SELECT DISTINCT tags.name FROM tags JOIN task_tags ON task_tags.tag_id = tags.id
+             JOIN tasks ON tasks.id = task_tags.task_id WHERE tasks.deleted_at IS NULL order by tags.name asc",
         )?;
-        stmt.query_row(params![task_id], |row| Task::try_from(row))
-            .with_context(|| format!("Couldn't get task at index {task_id}"))
+        let tags = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tags)
     }
 
-    pub fn get_all_task_by_highest_priority(&self) -> Vec<Task> {
+    /// Get the tasks matching `query`, ordered by id. Archived tasks are left out
+    pub fn find_tasks(&self, query: &Query) -> Result<Vec<Task>> {
+        let mut values = vec![];
+        let condition = query_condition(query, &mut values);
+        self.query_tasks(
+            &format!(
+                "SELECT {TASK_COLUMNS} FROM tasks WHERE deleted_at IS NULL AND archived_at IS NULL AND ({condition})
+                order by id asc"
+            ),
+            params_from_iter(values),
+        )
+    }
+
+    pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
+        self.query_tasks(&format!("SELECT {TASK_COLUMNS} FROM tasks WHERE deleted_at IS NULL"), [])
+    }
+
+    pub fn get_task_by_id(&self, task_id: i32) -> Result<Option<Task>> {
         let mut stmt = self
             .connection
-            .prepare("SELECT id, title, description, completed, priority, end_date FROM tasks order by priority asc")
-            .unwrap();
-        let task_row_iter = stmt
-            .query_map([], |row| Task::try_from(row))
-            .context("Couldn't get results from DB.")
-            .unwrap();
-        let mut tasks = Vec::new();
-        for task in task_row_iter {
-            tasks.push(task.unwrap());
+            .prepare(&format!("SELECT {TASK_COLUMNS} FROM tasks where id = ?1 AND deleted_at IS NULL"))?;
+        Ok(stmt
+            .query_row(params![task_id], |row| Task::try_from(row))
+            .optional()?)
+    }
+
+    /// Complete a task recording when, or open it again, which takes it out of the archive
+    pub fn toggle_task_completed(&self, task_id: i32, completed: bool) -> Result<usize> {
+        let completed = match completed {
+            true => 1,
+            false => 0,
+        };
+        Ok(self.connection.execute(
+            "UPDATE tasks SET completed = ?2, completed_at = CASE WHEN ?2 THEN coalesce(completed_at, ?3) END,
+                archived_at = CASE WHEN ?2 THEN archived_at END, updated_at = ?3
+             WHERE id = ?1",
+            params![task_id, completed, Utc::now()],
+        )?)
+    }
+
+    pub fn update_task_priority(&self, task_id: i32, priority: Priority) -> Result<usize> {
+        Ok(self.connection.execute(
+            "UPDATE tasks SET priority = ?2, updated_at = ?3 WHERE id = ?1",
+            params![task_id, priority as u8, Utc::now()],
+        )?)
+    }
+
+    pub fn update_task(&self, task: &Task) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let updated = tx.execute(
+            "UPDATE tasks SET title = ?2, description = ?3, end_date=?4, project_id = ?5, recurrence = ?6, updated_at = ?7,
+                due_at = ?8, due_tz = ?9 WHERE id = ?1",
+            params![task.id, task.title, task.description, task.date, task.project_id, task.recurrence, Utc::now(), due_at_column(task), due_tz_column(task)],
+        )?;
+        if updated > 0 {
+            Self::set_task_tags(&tx, task.id, &task.tags)?;
         }
-        tasks
+        tx.commit()?;
+        Ok(updated)
     }
 
-    pub fn get_all_task_by_lowest_priority(&self) -> Vec<Task> {
-        let mut stmt = self
-            .connection
-            .prepare("SELECT id, title, description, completed, priority, end_date FROM tasks order by priority desc")
-            .unwrap();
-        let task_row_iter = stmt
-            .query_map([], |row| Task::try_from(row))
-            .context("Couldn't get results from DB.")
-            .unwrap();
-        let mut tasks = Vec::new();
-        for task in task_row_iter {
-            tasks.push(task.unwrap());
+    /// Full-text search of `fts_query` in titles and descriptions, best matches first.
+    /// Archived tasks are left out
+    pub fn search(&self, fts_query: &str) -> Result<Vec<SearchHit>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {TASK_COLUMNS}, hits.title_highlight AS hit_title, hits.snippet AS hit_snippet, hits.rank AS hit_rank
+            FROM tasks JOIN (
+                SELECT rowid, highlight(tasks_fts, 0, ?2, ?3) AS title_highlight,
+                    snippet(tasks_fts, 1, ?2, ?3, '…', 12) AS snippet, bm25(tasks_fts, 10.0, 1.0) AS rank
+                FROM tasks_fts WHERE tasks_fts MATCH ?1
+            ) AS hits ON hits.rowid = tasks.id
+            WHERE tasks.deleted_at IS NULL AND tasks.archived_at IS NULL
+            order by hits.rank asc, tasks.id asc"
+        ))?;
+        let hits = stmt
+            .query_map(
+                params![fts_query, MATCH_START.to_string(), MATCH_END.to_string()],
+                |row| {
+                    Ok(SearchHit {
+                        task: Task::try_from(row)?,
+                        // by name, the position depends on the number of task columns
+                        title: row.get("hit_title")?,
+                        snippet: row.get::<_, Option<String>>("hit_snippet")?.unwrap_or_default(),
+                        rank: row.get("hit_rank")?,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    /// Get the direct subtasks of a task
+    pub fn get_subtasks(&self, task_id: i32) -> Result<Vec<Task>> {
+        self.query_tasks(
+            &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE parent_id = ?1 AND deleted_at IS NULL order by id asc"),
+            params![task_id],
+        )
+    }
+
+    /// Move a task to the trash, its subtasks are moved up one level, under the parent of the task
+    pub fn delete_task_reparenting(&self, task_id: i32) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE tasks SET parent_id = (SELECT parent_id FROM tasks WHERE id = ?1) WHERE parent_id = ?1 AND deleted_at IS NULL",
+            params![task_id],
+        )?;
+        let deleted = tx.execute(
+            "UPDATE tasks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![task_id, Utc::now()],
+        )?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Move a task to the trash together with its subtasks. Returns 0 if the
+    /// task doesn't exist or is already in the trash
+    pub fn delete_task(&self, task_id: i32) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let now = Utc::now();
+        let deleted = tx.execute(
+            "UPDATE tasks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![task_id, now],
+        )?;
+        if deleted > 0 {
+            // subtasks deleted before keep the instant they were deleted at
+            tx.execute(
+                &format!(
+                    "{SUBTREE}
+                    UPDATE tasks SET deleted_at = ?2 WHERE id IN (SELECT task_id FROM subtree) AND deleted_at IS NULL"
+                ),
+                params![task_id, now],
+            )?;
         }
-        tasks
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Get the tasks in the trash, the last deleted first
+    pub fn get_trash(&self) -> Result<Vec<Task>> {
+        self.query_tasks(
+            &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE deleted_at IS NOT NULL order by deleted_at desc, id asc"),
+            [],
+        )
+    }
+
+    /// Bring a task back from the trash with the subtasks deleted together with it.
+    /// If its parent is still in the trash the task goes back to the top level.
+    /// Returns 0 if the task is not in the trash
+    pub fn restore_from_trash(&self, task_id: i32) -> Result<usize> {
+        self.bring_back(task_id, "deleted_at")
+    }
+
+    /// Clear the instant in `column`, `deleted_at` or `archived_at`, of a task and
+    /// of the subtasks that got the same instant together with it. If its parent
+    /// still has one the task goes back to the top level. Returns 0 if the task has none
+    fn bring_back(&self, task_id: i32, column: &str) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let instant: Option<DateTime<Utc>> = tx
+            .query_row(
+                &format!("SELECT {column} FROM tasks WHERE id = ?1 AND {column} IS NOT NULL"),
+                params![task_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(instant) = instant else {
+            return Ok(0);
+        };
+        tx.execute(
+            &format!(
+                "{SUBTREE}
+                UPDATE tasks SET {column} = NULL WHERE id IN (SELECT task_id FROM subtree) AND {column} = ?2"
+            ),
+            params![task_id, instant],
+        )?;
+        Self::detach_from_parent_with(&tx, task_id, column)?;
+        tx.commit()?;
+        Ok(1)
+    }
+
+    /// Move a task to the top level if its parent has an instant in `column`,
+    /// `deleted_at` or `archived_at`
+    fn detach_from_parent_with(conn: &Connection, task_id: i32, column: &str) -> Result<()> {
+        conn.execute(
+            &format!(
+                "UPDATE tasks SET parent_id = NULL
+                WHERE id = ?1 AND parent_id IN (SELECT id FROM tasks WHERE {column} IS NOT NULL)"
+            ),
+            params![task_id],
+        )?;
+        Ok(())
+    }
+
+    /// Permanently delete a task, in the trash or not, with its subtasks
+    pub fn remove_task(&self, task_id: i32) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let removed = tx.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
+        tx.execute("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags)", [])?;
+        tx.commit()?;
+        Ok(removed)
     }
 
-    pub fn get_all_tasks_by_newest(&self) -> Vec<Task> {
+    /// Permanently delete a task of the trash with its subtasks. Returns 0 if the
+    /// task is not in the trash
+    pub fn purge_task(&self, task_id: i32) -> Result<usize> {
+        self.purge_where("id = ?1", params![task_id])
+    }
+
+    /// Permanently delete every task of the trash
+    pub fn empty_trash(&self) -> Result<usize> {
+        self.purge_where("1", [])
+    }
+
+    /// Permanently delete the tasks moved to the trash before `instant`
+    pub fn purge_trash_before(&self, instant: DateTime<Utc>) -> Result<usize> {
+        self.purge_where("deleted_at < ?1", params![instant])
+    }
+
+    /// Permanently delete the tasks of the trash matching `condition`, together
+    /// with the tags no other task uses
+    fn purge_where<P: Params>(&self, condition: &str, params: P) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let purged = tx.execute(
+            &format!("DELETE FROM tasks WHERE deleted_at IS NOT NULL AND ({condition})"),
+            params,
+        )?;
+        tx.execute("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags)", [])?;
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    /// Move a task to the archive together with its subtasks. Returns 0 if the
+    /// task doesn't exist, is in the trash, is already archived or has an open subtask
+    pub fn archive_task(&self, task_id: i32) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let archived = Self::archive_with(&tx, task_id, Utc::now())?;
+        tx.commit()?;
+        Ok(archived.min(1))
+    }
+
+    /// Archive the tasks completed before `instant` that have no open subtask at
+    /// any depth, together with their subtasks. Returns the number of archived tasks
+    pub fn archive_completed_before(&self, instant: DateTime<Utc>) -> Result<usize> {
+        let tx = self.connection.unchecked_transaction()?;
+        let ids = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM tasks
+                 WHERE completed = 1 AND completed_at < ?1 AND deleted_at IS NULL AND archived_at IS NULL
+                 order by id asc",
+            )?;
+            let ids = stmt
+                .query_map(params![instant], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<i32>>>()?;
+            ids
+        };
+        let now = Utc::now();
+        let mut archived = 0;
+        for task_id in ids {
+            archived += Self::archive_with(&tx, task_id, now)?;
+        }
+        tx.commit()?;
+        Ok(archived)
+    }
+
+    /// Whether a task has a subtask at any depth that is not completed, the ones
+    /// in the trash excluded
+    pub fn has_open_subtasks(&self, task_id: i32) -> Result<bool> {
+        Self::has_open_subtasks_with(&self.connection, task_id)
+    }
+
+    fn has_open_subtasks_with(conn: &Connection, task_id: i32) -> Result<bool> {
+        Ok(conn.query_row(
+            &format!(
+                "{SUBTREE}
+                SELECT EXISTS (SELECT 1 FROM tasks JOIN subtree ON tasks.id = subtree.task_id
+                    WHERE subtree.depth > 0 AND tasks.completed = 0 AND tasks.deleted_at IS NULL)"
+            ),
+            params![task_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Archive a task and its subtasks at `now`, unless it has an open subtask.
+    /// Returns the number of archived tasks, 0 if the task can't be archived
+    fn archive_with(conn: &Connection, task_id: i32, now: DateTime<Utc>) -> Result<usize> {
+        if Self::has_open_subtasks_with(conn, task_id)? {
+            return Ok(0);
+        }
+        let archived = conn.execute(
+            "UPDATE tasks SET archived_at = ?2 WHERE id = ?1 AND deleted_at IS NULL AND archived_at IS NULL",
+            params![task_id, now],
+        )?;
+        if archived == 0 {
+            return Ok(0);
+        }
+        let subtasks = conn.execute(
+            &format!(
+                "{SUBTREE}
+                UPDATE tasks SET archived_at = ?2
+                WHERE id IN (SELECT task_id FROM subtree) AND id != ?1 AND archived_at IS NULL AND deleted_at IS NULL"
+            ),
+            params![task_id, now],
+        )?;
+        Ok(archived + subtasks)
+    }
+
+    /// Get the archived tasks, the last archived first. Tasks in the trash are left out
+    pub fn get_archive(&self) -> Result<Vec<Task>> {
+        self.query_tasks(
+            &format!(
+                "SELECT {TASK_COLUMNS} FROM tasks WHERE archived_at IS NOT NULL AND deleted_at IS NULL
+                order by archived_at desc, id asc"
+            ),
+            [],
+        )
+    }
+
+    /// Take a task out of the archive with the subtasks archived together with it.
+    /// If its parent is still archived the task goes back to the top level.
+    /// Returns 0 if the task is not archived
+    pub fn unarchive_task(&self, task_id: i32) -> Result<usize> {
+        self.bring_back(task_id, "archived_at")
+    }
+
+    /// Number of tasks, the ones in the trash excluded
+    pub fn get_record_count(&self) -> Result<i64> {
+        let query = "SELECT count(*) FROM tasks WHERE deleted_at IS NULL";
+        Ok(self.connection.query_row(query, [], |r| r.get(0))?)
+    }
+
+    pub fn insert_project(&self, project: &Project) -> Result<i32> {
+        self.connection.execute(
+            "INSERT INTO projects (name) VALUES (?1)",
+            params![project.name.trim()],
+        )?;
+        Ok(self.connection.last_insert_rowid() as i32)
+    }
+
+    pub fn get_all_projects(&self) -> Result<Vec<Project>> {
         let mut stmt = self
             .connection
-            .prepare("SELECT id, title, description, completed, priority, end_date FROM tasks order by end_date desc")
-            .unwrap();
-        let task_row_iter = stmt
-            .query_map([], |row| Task::try_from(row))
-            .context("Couldn't get results from DB.")
-            .unwrap();
-        let mut tasks = Vec::new();
-        for task in task_row_iter {
-            tasks.push(task.unwrap());
+            .prepare("SELECT id, name FROM projects order by id asc")?;
+        let projects = stmt
+            .query_map([], |row| Project::try_from(row))?
+            .collect::<rusqlite::Result<Vec<Project>>>()?;
+        Ok(projects)
+    }
+
+    pub fn update_project(&self, project: &Project) -> Result<usize> {
+        Ok(self.connection.execute(
+            "UPDATE projects SET name = ?2 WHERE id = ?1",
+            params![project.id, project.name.trim()],
+        )?)
+    }
+
+    /// Delete a project moving its tasks to the trash of the default project
+    pub fn delete_project(&self, project_id: i32) -> Result<usize> {
+        if project_id == DEFAULT_PROJECT_ID {
+            return Err(Error::DefaultProjectDeletion);
         }
-        tasks
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE tasks SET deleted_at = ?2 WHERE project_id = ?1 AND deleted_at IS NULL",
+            params![project_id, Utc::now()],
+        )?;
+        // the tasks would go with the project, in the trash they belong to the default one
+        tx.execute(
+            "UPDATE tasks SET project_id = ?2 WHERE project_id = ?1",
+            params![project_id, DEFAULT_PROJECT_ID],
+        )?;
+        let deleted = tx.execute("DELETE FROM projects WHERE id = ?1", params![project_id])?;
+        tx.commit()?;
+        Ok(deleted)
     }
 
-    pub fn get_all_tasks_by_oldest(&self) -> Vec<Task> {
+    pub fn insert_view(&self, view: &View) -> Result<i32> {
+        self.connection.execute(
+            "INSERT INTO views (name, query, sort_by, sort_order) VALUES (?1, ?2, ?3, ?4)",
+            params![view.name.trim(), view.query.trim(), view.sort_by, view.sort_order],
+        )?;
+        Ok(self.connection.last_insert_rowid() as i32)
+    }
+
+    pub fn get_all_views(&self) -> Result<Vec<View>> {
         let mut stmt = self
             .connection
-            .prepare("SELECT id, title, description, completed, priority, end_date FROM tasks order by end_date asc")
-            .unwrap();
-        let task_row_iter = stmt
-            .query_map([], |row| Task::try_from(row))
-            .context("Couldn't get results from DB.")
-            .unwrap();
-        let mut tasks = Vec::new();
-        for task in task_row_iter {
-            tasks.push(task.unwrap());
-        }
-        tasks
+            .prepare("SELECT id, name, query, sort_by, sort_order FROM views order by id asc")?;
+        let views = stmt
+            .query_map([], |row| View::try_from(row))?
+            .collect::<rusqlite::Result<Vec<View>>>()?;
+        Ok(views)
     }
 
-    pub fn toggle_task_completed(&self, task_id: i32, completed: bool) -> usize {
-        let completed = match completed {
-            true => 1,
-            false => 0,
-        };
-        self.connection
-            .execute(
-                "UPDATE tasks SET completed = ?2 WHERE id = ?1",
-                params![task_id, completed],
-            )
-            .context("Can't update the task completed property.")
-            .unwrap()
+    pub fn update_view(&self, view: &View) -> Result<usize> {
+        Ok(self.connection.execute(
+            "UPDATE views SET name = ?2, query = ?3, sort_by = ?4, sort_order = ?5 WHERE id = ?1",
+            params![view.id, view.name.trim(), view.query.trim(), view.sort_by, view.sort_order],
+        )?)
     }
 
-    pub fn update_task_priority(&self, task_id: i32, priority: Priority) -> usize {
-        self.connection
-            .execute(
-                "UPDATE tasks SET priority = ?2 WHERE id = ?1",
-                params![task_id, priority as u8],
-            )
-            .context("Can't update the task priority property.")
-            .unwrap()
+    pub fn delete_view(&self, view_id: i32) -> Result<usize> {
+        if view_id == DEFAULT_VIEW_ID {
+            return Err(Error::DefaultViewDeletion);
+        }
+        Ok(self
+            .connection
+            .execute("DELETE FROM views WHERE id = ?1", params![view_id])?)
     }
 
-    pub fn update_task(&self, task: &Task) -> usize {
-        self.connection
-            .execute(
-                "UPDATE tasks SET title = ?2, description = ?3, end_date=?4 WHERE id = ?1",
-                params![task.id, task.title, task.description, task.date],
-            )
-            .context("Can't update the task.")
-            .unwrap()
+    /// Move every task to the trash, archived ones too
+    pub fn clear(&self) -> Result<usize> {
+        Ok(self.connection.execute(
+            "UPDATE tasks SET deleted_at = ?1 WHERE deleted_at IS NULL",
+            params![Utc::now()],
+        )?)
     }
+}
 
-    pub fn delete_task(&self, task_id: i32) -> usize {
-        self.connection
-            .execute("delete from tasks where id = ?1", params![task_id])
-            .context("Can't delete the task.")
-            .unwrap()
+/// Compile `query` to an sql condition on the `tasks` table, the values it
+/// refers to with `?` are appended to `values`
+fn query_condition(query: &Query, values: &mut Vec<Value>) -> String {
+    let join = |queries: &[Query], operator: &str, empty: &str, values: &mut Vec<Value>| {
+        if queries.is_empty() {
+            return empty.to_string();
+        }
+        let conditions: Vec<String> = queries.iter().map(|q| query_condition(q, values)).collect();
+        format!("({})", conditions.join(operator))
+    };
+    match query {
+        Query::And(queries) => join(queries, " AND ", "1", values),
+        Query::Or(queries) => join(queries, " OR ", "0", values),
+        // a NULL condition, like a comparison with a missing due date, doesn't match
+        // so its negation does
+        Query::Not(query) => format!("NOT coalesce({}, 0)", query_condition(query, values)),
+        Query::Term(term) => term_condition(term, values),
     }
+}
 
-    pub fn get_record_count(&self) -> i64 {
-        let query = "SELECT count(*) FROM tasks";
-        self.connection
-            .query_row(query, [], |r| r.get(0))
-            .context("Can't get record count")
-            .unwrap()
+fn term_condition(term: &Term, values: &mut Vec<Value>) -> String {
+    match term {
+        Term::Priority(comparison, priority) => {
+            values.push(Value::Integer(priority.to_u8().into()));
+            // stored as 1 for high to 3 for low, higher priorities are smaller numbers
+            let operator = match comparison {
+                Comparison::Equal => "=",
+                Comparison::Less => ">",
+                Comparison::LessOrEqual => ">=",
+                Comparison::Greater => "<",
+                Comparison::GreaterOrEqual => "<=",
+            };
+            format!("tasks.priority {operator} ?")
+        }
+        Term::Completed(completed) => {
+            values.push(Value::Integer((*completed).into()));
+            "tasks.completed = ?".to_string()
+        }
+        Term::Tag(tag) => {
+            values.push(Value::Text(tag.clone()));
+            "tasks.id IN (SELECT task_tags.task_id FROM task_tags JOIN tags ON tags.id = task_tags.tag_id
+                WHERE tags.name = ?)"
+                .to_string()
+        }
+        Term::Project(name) => {
+            values.push(Value::Text(name.clone()));
+            "tasks.project_id IN (SELECT id FROM projects WHERE name = ? COLLATE NOCASE)".to_string()
+        }
+        Term::ProjectId(project_id) => {
+            values.push(Value::Integer((*project_id).into()));
+            "tasks.project_id = ?".to_string()
+        }
+        Term::Due(comparison, date) => {
+            let operator = match comparison {
+                Comparison::Equal => "=",
+                Comparison::Less => "<",
+                Comparison::LessOrEqual => "<=",
+                Comparison::Greater => ">",
+                Comparison::GreaterOrEqual => ">=",
+            };
+            values.push(Value::Text(date.format(ISO_DATE_FORMAT).to_string()));
+            // tasks without a due date never match, NULL compares false
+            format!("tasks.end_date {operator} ?")
+        }
+        Term::HasDue(true) => "tasks.end_date IS NOT NULL".to_string(),
+        Term::HasDue(false) => "tasks.end_date IS NULL".to_string(),
+        Term::Recurring(true) => "tasks.recurrence IS NOT NULL".to_string(),
+        Term::Recurring(false) => "tasks.recurrence IS NULL".to_string(),
+        Term::Text(text) => match fts_query(text) {
+            Some(fts_query) => {
+                values.push(Value::Text(fts_query));
+                "tasks.id IN (SELECT rowid FROM tasks_fts WHERE tasks_fts MATCH ?)".to_string()
+            }
+            None => "1".to_string(),
+        },
     }
+}
 
-    pub fn clear(&self) -> usize {
-        self.connection
-            .execute("DELETE FROM tasks", [])
-            .context("Can't clear database")
-            .unwrap()
+/// Value of the `due_at` column: the instant the task is due in UTC as ISO-8601,
+/// which sorts chronologically
+fn due_at_column(task: &Task) -> Option<String> {
+    task.due_at()
+        .map(|due_at| due_at.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Value of the `due_tz` column: the IANA name of the timezone of the due time
+fn due_tz_column(task: &Task) -> Option<&'static str> {
+    task.due_time.and(task.timezone).map(|timezone| timezone.name())
+}
+
+/// `uid`, or a new random uuid if it is empty
+fn new_uid_if_empty(uid: &str) -> String {
+    match uid {
+        "" => uuid::Uuid::new_v4().to_string(),
+        uid => uid.to_string(),
     }
 }
 
@@ -209,13 +760,77 @@ impl TryFrom<&Row<'_>> for Task {
     type Error = rusqlite::Error;
 
     fn try_from(row: &Row<'_>) -> rusqlite::Result<Self, Self::Error> {
-        Ok(Task {
+        let priority: u8 = row.get(4)?;
+        let timezone = row
+            .get::<_, Option<String>>(14)?
+            .map(|name| {
+                name.parse::<Tz>().map_err(|_| {
+                    rusqlite::Error::FromSqlConversionFailure(14, Type::Text, Box::new(Error::InvalidTimezone(name)))
+                })
+            })
+            .transpose()?;
+        let mut task = Task {
             id: row.get(0)?,
             title: row.get(1)?,
             description: row.get(2)?,
             completed: row.get(3)?,
-            priority: Priority::from_u8(row.get(4)?).expect("Invalid priority"),
-            date: TaskDate::column_result(row.get_ref(5)?).unwrap_or(TaskDate(None)),
+            priority: Priority::from_u8(priority).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    4,
+                    Type::Integer,
+                    Box::new(Error::InvalidPriority(priority)),
+                )
+            })?,
+            date: row.get(5)?,
+            tags: row
+                .get::<_, Option<String>>(6)?
+                .map(|tags| Task::parse_tags(&tags))
+                .unwrap_or_default(),
+            project_id: row
+                .get::<_, Option<i32>>(7)?
+                .unwrap_or(DEFAULT_PROJECT_ID),
+            parent_id: row.get(8)?,
+            recurrence: row.get(9)?,
+            uid: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+            created_at: row.get(11)?,
+            updated_at: row.get(12)?,
+            due_time: None,
+            timezone,
+            deleted_at: row.get(15)?,
+            completed_at: row.get(16)?,
+            archived_at: row.get(17)?,
+        };
+        // the stored instant is the reference, the day can change with the timezone
+        if let Some(due_at) = row.get::<_, Option<DateTime<Utc>>>(13)? {
+            let due_at = due_at.with_timezone(&timezone.unwrap_or_else(default_timezone));
+            task.date = TaskDate(Some(due_at.date_naive()));
+            task.due_time = Some(due_at.time());
+        }
+        Ok(task)
+    }
+}
+
+impl TryFrom<&Row<'_>> for Project {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> rusqlite::Result<Self, Self::Error> {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    }
+}
+
+impl TryFrom<&Row<'_>> for View {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> rusqlite::Result<Self, Self::Error> {
+        Ok(View {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            query: row.get(2)?,
+            sort_by: row.get(3)?,
+            sort_order: row.get(4)?,
         })
     }
 }
@@ -225,11 +840,14 @@ impl FromSql for TaskDate {
         match value {
             ValueRef::Null => Ok(TaskDate(None)),
             ValueRef::Text(text) => {
-                let date_str = std::str::from_utf8(text).unwrap();
-                let date = NaiveDate::parse_from_str(date_str, DATE_FORMAT).unwrap();
+                let date_str =
+                    std::str::from_utf8(text).map_err(|e| FromSqlError::Other(Box::new(e)))?;
+                let date = NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT).map_err(|_| {
+                    FromSqlError::Other(Box::new(Error::InvalidDate(date_str.to_string())))
+                })?;
                 Ok(TaskDate(Some(date)))
             }
-            _ => Err(rusqlite::types::FromSqlError::InvalidType.into()),
+            _ => Err(FromSqlError::InvalidType),
         }
     }
 }
@@ -237,8 +855,60 @@ impl FromSql for TaskDate {
 impl ToSql for TaskDate {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         match self.0 {
-            Some(date) => Ok(ToSqlOutput::from(date.format(DATE_FORMAT).to_string())),
+            Some(date) => Ok(ToSqlOutput::from(date.format(ISO_DATE_FORMAT).to_string())),
             None => Ok(ToSqlOutput::from(rusqlite::types::Null)),
         }
     }
 }
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let rule = value.as_str()?;
+        Recurrence::from_rrule(rule)
+            .map_err(|_| FromSqlError::Other(Box::new(Error::InvalidRecurrence(rule.to_string()))))
+    }
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_db_string()))
+    }
+}
+
+impl FromSql for SortBy {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "priority" => Ok(SortBy::Priority),
+            "date" => Ok(SortBy::Date),
+            other => Err(FromSqlError::Other(Box::new(Error::InvalidSort(other.to_string())))),
+        }
+    }
+}
+
+impl ToSql for SortBy {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(match self {
+            SortBy::Priority => "priority",
+            SortBy::Date => "date",
+        }))
+    }
+}
+
+impl FromSql for SortOrder {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "high" => Ok(SortOrder::High),
+            "low" => Ok(SortOrder::Low),
+            other => Err(FromSqlError::Other(Box::new(Error::InvalidSort(other.to_string())))),
+        }
+    }
+}
+
+impl ToSql for SortOrder {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(match self {
+            SortOrder::High => "high",
+            SortOrder::Low => "low",
+        }))
+    }
+}
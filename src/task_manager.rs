@@ -1,12 +1,48 @@
-use crate::task::{Priority, Task};
+use crate::error::Result;
+use crate::project::Project;
+use crate::query::Query;
+use crate::search::{fts_query, SearchHit};
+use crate::task::{Priority, Task, TaskNode};
 use crate::task_db::DB;
+use crate::view::View;
+use chrono::{Duration, Local, Utc};
+use serde::Deserialize;
+use std::cmp::Ordering;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SortOrder {
     High,
     Low,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    Priority,
+    Date,
+}
+
+impl SortBy {
+    /// Compare two tasks, `SortOrder::High` puts the highest priority or the newest date first.
+    /// Tasks without a due date sort after the others by date, whatever the order
+    pub fn compare(&self, a: &Task, b: &Task, sort: SortOrder) -> Ordering {
+        let ordering = match self {
+            // `Priority::High` has the lowest discriminant
+            SortBy::Priority => b.priority.cmp(&a.priority),
+            SortBy::Date => match (a.local_due(), b.local_due()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                // tasks without a due date come last in both orders
+                (a, b) => return a.is_none().cmp(&b.is_none()),
+            },
+        };
+        match sort {
+            SortOrder::High => ordering.reverse(),
+            SortOrder::Low => ordering,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct TasksService {
@@ -16,7 +52,7 @@ pub struct TasksService {
 
 impl Default for TasksService {
     fn default() -> Self {
-        Self::new(String::new())
+        Self::new(String::new()).expect("Can't open in-memory DB.")
     }
 }
 
@@ -25,82 +61,257 @@ impl TasksService {
     /// `db_path` is the path to the database file. If it doesn't exist it is going to be created.
     /// If db_path is an empty string __""__, an in memory instance of database is going to be
     /// created instead.
-    pub fn new(db_path: String) -> Self {
-        Self {
-            db: DB::create_and_return_connection(db_path.as_str()),
+    pub fn new(db_path: String) -> Result<Self> {
+        Ok(Self {
+            db: DB::create_and_return_connection(db_path.as_str())?,
             db_filename: db_path,
+        })
+    }
+
+    /// Add a new task, returns the id assigned to it
+    pub fn add_new_task(&self, task: &Task) -> Result<i32> {
+        self.db.insert_task(task)
+    }
+
+    /// Add tasks coming from another source, e.g. an imported calendar, keeping
+    /// their completed status. Tasks with the uid of a stored task update it
+    /// instead of adding a copy. Returns the ids of the tasks
+    pub fn import_tasks(&self, tasks: &[Task]) -> Result<Vec<i32>> {
+        self.db.upsert_tasks_by_uid(tasks)
+    }
+
+    /// Search the words of `query` in titles and descriptions, best matches first.
+    /// Every word must match the start of a word of the task
+    pub fn search(&self, query: &str) -> Result<Vec<SearchHit>> {
+        match fts_query(query) {
+            Some(fts_query) => self.db.search(&fts_query),
+            None => Ok(vec![]),
         }
     }
 
-    pub fn add_new_task(&self, task: &Task) {
-        self.db.insert_task(task);
+    /// Get the task with the given uid
+    pub fn get_task_by_uid(&self, uid: &str) -> Result<Option<Task>> {
+        self.db.get_task_by_uid(uid)
     }
 
     /// Get a task with `task_id`. Returns an Option containing the task or None
     /// if it couldn't find the task.
-    pub fn get_task(&self, task_id: i32) -> Option<Task> {
-        match self.db.get_task_by_id(task_id) {
-            Ok(t) => Some(t),
-            Err(e) => {
-                eprintln!("{e}");
-                if let Some(cause) = e.source() {
-                    eprintln!("Underlying cause: {:?}", cause);
-                }
-                None
-            }
-        }
+    pub fn get_task(&self, task_id: i32) -> Result<Option<Task>> {
+        self.db.get_task_by_id(task_id)
     }
 
     /// Returns all the tasks present in the database
-    pub fn get_all_tasks(&self) -> Vec<Task> {
+    pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
         self.db.get_all_tasks()
     }
 
-    /// Return all the tasks sorted by `sort`
-    pub fn get_all_tasks_sorted_by_priority(&self, sort: SortOrder) -> Vec<Task> {
-        match sort {
-            SortOrder::High => self.db.get_all_task_by_highest_priority(),
-            SortOrder::Low => self.db.get_all_task_by_lowest_priority(),
+    /// Return the tasks matching `query` sorted by `sort_by` and `sort`,
+    /// tasks that compare equal stay in the order they were added. Archived tasks are left out
+    pub fn find_tasks(&self, query: &Query, sort_by: SortBy, sort: SortOrder) -> Result<Vec<Task>> {
+        let mut tasks = self.db.find_tasks(query)?;
+        tasks.sort_by(|a, b| sort_by.compare(a, b, sort));
+        Ok(tasks)
+    }
+
+    /// Return the tasks of a project matching `filter` arranged in trees of subtasks.
+    /// A matching subtask whose parent doesn't match is shown as a root.
+    /// Roots, and the subtasks of each task, are sorted by `sort_by` and `sort`
+    pub fn get_task_tree(&self, project_id: i32, filter: &Query, sort_by: SortBy, sort: SortOrder) -> Result<Vec<TaskNode>> {
+        let tasks = self.find_tasks(&filter.in_project(project_id), sort_by, sort)?;
+        Ok(TaskNode::build_forest(tasks))
+    }
+
+    /// Return the direct subtasks of a task
+    pub fn get_subtasks(&self, task_id: i32) -> Result<Vec<Task>> {
+        self.db.get_subtasks(task_id)
+    }
+
+    /// Names of all the tags in use, sorted alphabetically
+    pub fn get_all_tags(&self) -> Result<Vec<String>> {
+        self.db.get_all_tags()
+    }
+
+    /// Mark a task as completed or not. Completing a recurring task adds its
+    /// next occurrence, the completed one stops repeating
+    pub fn toggle_task_status(&self, task_id: i32, completed: bool) -> Result<usize> {
+        if completed && self.complete_recurring_task(task_id)?.is_some() {
+            return Ok(1);
         }
+        self.db.toggle_task_completed(task_id, completed)
     }
 
-    pub fn get_all_tasks_sorted_by_date(&self, sort: SortOrder) -> Vec<Task> {
-        match sort {
-            SortOrder::High => self.db.get_all_tasks_by_newest(),
-            SortOrder::Low => self.db.get_all_tasks_by_oldest(),
+    /// Complete a recurring task adding its next occurrence. Returns the id of
+    /// the next occurrence, None if the task doesn't exist or doesn't repeat
+    pub fn complete_recurring_task(&self, task_id: i32) -> Result<Option<i32>> {
+        let Some(task) = self.db.get_task_by_id(task_id)? else {
+            return Ok(None);
+        };
+        match task.next_occurrence(Local::now().date_naive()) {
+            Some(next) => Ok(Some(self.db.complete_and_reschedule(task_id, &next)?)),
+            None => Ok(None),
         }
     }
 
-    pub fn toggle_task_status(&self, task_id: i32, completed: bool) -> usize {
-        self.db.toggle_task_completed(task_id, completed)
+    /// Get the tasks with the given ids, in the same order. Missing tasks are skipped
+    pub fn get_tasks(&self, task_ids: &[i32]) -> Result<Vec<Task>> {
+        let mut tasks = vec![];
+        for id in task_ids {
+            if let Some(task) = self.db.get_task_by_id(*id)? {
+                tasks.push(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Get a task followed by all its subtasks, parents come before their subtasks
+    pub fn get_task_with_subtasks(&self, task_id: i32) -> Result<Vec<Task>> {
+        self.db.get_task_with_subtasks(task_id)
+    }
+
+    /// Write a task exactly as it is, id and status included, creating it if
+    /// it doesn't exist. Used to bring back a previous state of a task
+    pub fn restore_task(&self, task: &Task) -> Result<usize> {
+        self.db.restore_task(task)
     }
 
     /// Change priority of the task
-    pub fn change_priority(&self, task_id: i32, priority: &Priority) -> usize {
+    pub fn change_priority(&self, task_id: i32, priority: &Priority) -> Result<usize> {
         self.db.update_task_priority(task_id, priority.to_owned())
     }
 
-    pub fn update_task(&self, task: &Task) {
-        self.db.update_task(task);
+    pub fn update_task(&self, task: &Task) -> Result<usize> {
+        self.db.update_task(task)
     }
 
-    /// Delete a task with `task_id` number
-    pub fn delete_task(&self, task_id: i32) -> usize {
+    /// Move a task with `task_id` number to the trash together with all its subtasks
+    pub fn delete_task(&self, task_id: i32) -> Result<usize> {
         self.db.delete_task(task_id)
     }
 
-    /// Number of tasks present in the database
-    pub fn length(&self) -> i64 {
+    /// Move a task with `task_id` number to the trash, its subtasks are moved
+    /// under the parent of the deleted task
+    pub fn delete_task_keeping_subtasks(&self, task_id: i32) -> Result<usize> {
+        self.db.delete_task_reparenting(task_id)
+    }
+
+    /// Returns the tasks in the trash, the last deleted first
+    pub fn get_trash(&self) -> Result<Vec<Task>> {
+        self.db.get_trash()
+    }
+
+    /// Bring a task back from the trash with the subtasks deleted together with it
+    pub fn restore_from_trash(&self, task_id: i32) -> Result<usize> {
+        self.db.restore_from_trash(task_id)
+    }
+
+    /// Permanently delete a task with its subtasks, wherever it is. Used to undo
+    /// the creation of a task
+    pub fn remove_task(&self, task_id: i32) -> Result<usize> {
+        self.db.remove_task(task_id)
+    }
+
+    /// Permanently delete a task of the trash with its subtasks
+    pub fn purge_task(&self, task_id: i32) -> Result<usize> {
+        self.db.purge_task(task_id)
+    }
+
+    /// Permanently delete every task of the trash
+    pub fn empty_trash(&self) -> Result<usize> {
+        self.db.empty_trash()
+    }
+
+    /// Permanently delete the tasks in the trash for more than `retention_days`,
+    /// 0 keeps them forever
+    pub fn purge_expired_trash(&self, retention_days: u32) -> Result<usize> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+        self.db.purge_trash_before(Utc::now() - Duration::days(retention_days as i64))
+    }
+
+    /// Move a task to the archive together with its subtasks, unless one of them is open
+    pub fn archive_task(&self, task_id: i32) -> Result<usize> {
+        self.db.archive_task(task_id)
+    }
+
+    /// Whether a task has a subtask at any depth that is not completed
+    pub fn has_open_subtasks(&self, task_id: i32) -> Result<bool> {
+        self.db.has_open_subtasks(task_id)
+    }
+
+    /// Returns the archived tasks, the last archived first
+    pub fn get_archive(&self) -> Result<Vec<Task>> {
+        self.db.get_archive()
+    }
+
+    /// Take a task out of the archive with the subtasks archived together with it
+    pub fn unarchive_task(&self, task_id: i32) -> Result<usize> {
+        self.db.unarchive_task(task_id)
+    }
+
+    /// Archive the tasks completed more than `after_days` ago, 0 archives nothing
+    pub fn archive_completed(&self, after_days: u32) -> Result<usize> {
+        if after_days == 0 {
+            return Ok(0);
+        }
+        self.db.archive_completed_before(Utc::now() - Duration::days(after_days as i64))
+    }
+
+    /// Create a new project, returns the id assigned to it
+    pub fn add_new_project(&self, project: &Project) -> Result<i32> {
+        self.db.insert_project(project)
+    }
+
+    /// Returns all the projects, the default one first
+    pub fn get_all_projects(&self) -> Result<Vec<Project>> {
+        self.db.get_all_projects()
+    }
+
+    /// Rename a project
+    pub fn update_project(&self, project: &Project) -> Result<usize> {
+        self.db.update_project(project)
+    }
+
+    /// Delete a project moving its tasks to the trash, where they belong to the
+    /// default project. The default project can't be deleted
+    pub fn delete_project(&self, project_id: i32) -> Result<usize> {
+        self.db.delete_project(project_id)
+    }
+
+    /// Save a new view, returns its id. Fails if the filter of the view is invalid
+    pub fn add_new_view(&self, view: &View) -> Result<i32> {
+        view.parse_query()?;
+        self.db.insert_view(view)
+    }
+
+    /// Returns all the saved views, the default one showing every task first
+    pub fn get_all_views(&self) -> Result<Vec<View>> {
+        self.db.get_all_views()
+    }
+
+    /// Change the name, filter or sort order of a view
+    pub fn update_view(&self, view: &View) -> Result<usize> {
+        view.parse_query()?;
+        self.db.update_view(view)
+    }
+
+    /// Delete a view, the default one can't be deleted
+    pub fn delete_view(&self, view_id: i32) -> Result<usize> {
+        self.db.delete_view(view_id)
+    }
+
+    /// Number of tasks, the ones in the trash excluded
+    pub fn length(&self) -> Result<i64> {
         self.db.get_record_count()
     }
 
     /// Check if there are no tasks at all
-    pub fn is_empty(&self) -> bool {
-        self.length() == 0
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.length()? == 0)
     }
 
-    /// Clean the database, delete all tasks
-    pub fn delete_all_tasks(&self) -> usize {
+    /// Move the tasks of every project to the trash
+    pub fn delete_all_tasks(&self) -> Result<usize> {
         self.db.clear()
     }
 }
@@ -0,0 +1,289 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Text being typed, with a cursor that moves by grapheme, so accented
+/// letters and emoji are edited as one character. It is a single line unless
+/// created with `multiline()`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset of the cursor in `text`, always at a grapheme boundary
+    cursor: usize,
+    /// Enter inserts a line break, Up and Down move between lines
+    multiline: bool,
+}
+
+impl From<&str> for TextInput {
+    fn from(text: &str) -> Self {
+        TextInput::from(text.to_string())
+    }
+}
+
+/// The cursor is placed at the end of the text
+impl From<String> for TextInput {
+    fn from(text: String) -> Self {
+        let cursor = text.len();
+        TextInput { text, cursor, multiline: false }
+    }
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty input of several lines
+    pub fn multiline() -> Self {
+        TextInput { multiline: true, ..Self::default() }
+    }
+
+    /// Replace the text, placing the cursor at its end
+    pub fn set_value(&mut self, text: &str) {
+        self.text.clear();
+        self.cursor = 0;
+        self.insert_str(text);
+    }
+
+    pub fn value(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the cursor in `value()`
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Take the text out of the input, leaving it empty
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert pasted text at the cursor. Line breaks become spaces in a single
+    /// line input, other control characters are dropped
+    pub fn insert_str(&mut self, text: &str) {
+        let separator = if self.multiline { "\n" } else { " " };
+        let text: String = text
+            .lines()
+            .collect::<Vec<_>>()
+            .join(separator)
+            .chars()
+            .filter(|c| *c == '\n' || !c.is_control())
+            .collect();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    /// Move to the start of the line
+    pub fn move_home(&mut self) {
+        self.cursor = self.line_start();
+    }
+
+    /// Move to the end of the line
+    pub fn move_end(&mut self) {
+        self.cursor = self.line_end();
+    }
+
+    /// Move to the line above, at the same column when it is long enough
+    pub fn move_up(&mut self) {
+        let start = self.line_start();
+        if start > 0 {
+            let above = self.text[..start - 1].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            self.cursor = self.offset_at_width(above, start - 1, self.cursor_width());
+        }
+    }
+
+    /// Move to the line below, at the same column when it is long enough
+    pub fn move_down(&mut self) {
+        let end = self.line_end();
+        if end < self.text.len() {
+            let below_end = self.text[end + 1..].find('\n').map(|i| end + 1 + i).unwrap_or(self.text.len());
+            self.cursor = self.offset_at_width(end + 1, below_end, self.cursor_width());
+        }
+    }
+
+    /// Move to the start of the word before the cursor
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.previous_word_start();
+    }
+
+    /// Move to the end of the word after the cursor
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.next_word_end();
+    }
+
+    /// Delete the character before the cursor, like Backspace
+    pub fn delete_before(&mut self) {
+        let start = self.previous_boundary();
+        self.delete_range(start, self.cursor);
+    }
+
+    /// Delete the character after the cursor, like Delete
+    pub fn delete_after(&mut self) {
+        let end = self.next_boundary();
+        self.delete_range(self.cursor, end);
+    }
+
+    pub fn delete_word_before(&mut self) {
+        let start = self.previous_word_start();
+        self.delete_range(start, self.cursor);
+    }
+
+    pub fn delete_word_after(&mut self) {
+        let end = self.next_word_end();
+        self.delete_range(self.cursor, end);
+    }
+
+    /// Delete from the start of the line to the cursor
+    pub fn delete_to_start(&mut self) {
+        self.delete_range(self.line_start(), self.cursor);
+    }
+
+    /// Delete from the cursor to the end of the line
+    pub fn delete_to_end(&mut self) {
+        self.delete_range(self.cursor, self.line_end());
+    }
+
+    /// Line of the cursor, starting from 0
+    pub fn cursor_row(&self) -> usize {
+        self.text[..self.cursor].matches('\n').count()
+    }
+
+    /// Columns the text before the cursor on its line takes on screen, wide
+    /// characters like CJK and emoji take two
+    pub fn cursor_width(&self) -> usize {
+        self.text[self.line_start()..self.cursor].width()
+    }
+
+    /// Columns to scroll the text by to keep the cursor inside a field `width` columns wide
+    pub fn scroll_offset(&self, width: u16) -> u16 {
+        let visible = (width as usize).saturating_sub(1);
+        self.cursor_width().saturating_sub(visible).min(u16::MAX as usize) as u16
+    }
+
+    /// Lines to scroll the text by to keep the cursor inside a field `height` lines high
+    pub fn scroll_rows(&self, height: u16) -> u16 {
+        let visible = (height as usize).saturating_sub(1);
+        self.cursor_row().saturating_sub(visible).min(u16::MAX as usize) as u16
+    }
+
+    /// Edit the text with `key`, returns false when the key doesn't edit text,
+    /// like Tab, or Enter in a single line input
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.move_home(),
+            KeyCode::Char('e') if ctrl => self.move_end(),
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('k') if ctrl => self.delete_to_end(),
+            KeyCode::Char('b') if alt => self.move_word_left(),
+            KeyCode::Char('f') if alt => self.move_word_right(),
+            KeyCode::Char('d') if alt => self.delete_word_after(),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_char(c),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_before(),
+            KeyCode::Backspace => self.delete_before(),
+            KeyCode::Delete if ctrl || alt => self.delete_word_after(),
+            KeyCode::Delete => self.delete_after(),
+            KeyCode::Left if ctrl || alt => self.move_word_left(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right if ctrl || alt => self.move_word_right(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            KeyCode::Enter if self.multiline => self.insert_char('\n'),
+            KeyCode::Up if self.multiline => self.move_up(),
+            KeyCode::Down if self.multiline => self.move_down(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map(|i| i + 1).unwrap_or(0)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..].find('\n').map(|i| self.cursor + i).unwrap_or(self.text.len())
+    }
+
+    /// Offset of the last grapheme boundary of the line between `start` and `end`
+    /// whose column is not past `width`
+    fn offset_at_width(&self, start: usize, end: usize, width: usize) -> usize {
+        let mut offset = start;
+        for (index, grapheme) in self.text[start..end].grapheme_indices(true) {
+            if self.text[start..start + index + grapheme.len()].width() > width {
+                break;
+            }
+            offset = start + index + grapheme.len();
+        }
+        offset
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|grapheme| self.cursor + grapheme.len())
+            .unwrap_or(self.text.len())
+    }
+
+    /// Words with their byte offset, punctuation and spaces between them are skipped
+    fn words(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.text
+            .split_word_bound_indices()
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+    }
+
+    fn previous_word_start(&self) -> usize {
+        self.words()
+            .map(|(index, _)| index)
+            .filter(|index| *index < self.cursor)
+            .last()
+            .unwrap_or(0)
+    }
+
+    fn next_word_end(&self) -> usize {
+        self.words()
+            .map(|(index, word)| index + word.len())
+            .find(|end| *end > self.cursor)
+            .unwrap_or(self.text.len())
+    }
+}
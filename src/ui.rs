@@ -1,33 +1,84 @@
-use crate::app::{AppContext, InputFieldType, InputMode};
+use crate::app::{AppContext, Confirmation, InputFieldType, InputMode, TreeRow};
+use crate::config::Theme;
+use crate::date::{date_format_hint, format_due, format_timestamp, input_date_format, Due};
+use crate::keymap::{Action, KeySequence};
+use crate::markdown::markdown_lines;
+use crate::search::{highlighted_parts, SearchHit};
 use crate::task::{Priority, Task};
+use crate::task_manager::{SortBy, SortOrder};
+use crate::text_input::TextInput;
 use ratatui::layout::{Constraint, Flex, Layout, Position, Rect};
 use ratatui::prelude::{Color, Direction, Line, Modifier, Span, StatefulWidget, Style};
 use ratatui::style::palette::tailwind::{BLUE, SLATE};
 use ratatui::style::Stylize;
-use ratatui::widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, Paragraph, Tabs, Wrap};
 use ratatui::{symbols, Frame};
 use std::vec;
-const TODO_HEADER_STYLE: Style = Style::new().fg(SLATE.c100).bg(BLUE.c800);
-const NORMAL_ROW_BG: Color = SLATE.c950;
-const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
-const TEXT_FG_COLOR: Color = SLATE.c200;
-const COMPLETED_TEXT_FG_COLOR: Color = SLATE.c500;
+use unicode_width::UnicodeWidthStr;
+/// Colors of a `Theme`
+struct Palette {
+    header: Style,
+    row_bg: Color,
+    selected: Style,
+    text_fg: Color,
+    completed_text_fg: Color,
+    /// Background of the tags of completed tasks
+    completed_tag_bg: Color,
+}
+
+const DARK_PALETTE: Palette = Palette {
+    header: Style::new().fg(SLATE.c100).bg(BLUE.c800),
+    row_bg: SLATE.c950,
+    selected: Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD),
+    text_fg: SLATE.c200,
+    completed_text_fg: SLATE.c500,
+    completed_tag_bg: SLATE.c800,
+};
+
+const LIGHT_PALETTE: Palette = Palette {
+    header: Style::new().fg(SLATE.c50).bg(BLUE.c600),
+    row_bg: SLATE.c50,
+    selected: Style::new().bg(SLATE.c200).add_modifier(Modifier::BOLD),
+    text_fg: SLATE.c900,
+    completed_text_fg: SLATE.c400,
+    completed_tag_bg: SLATE.c200,
+};
+
+fn palette(app: &AppContext) -> &'static Palette {
+    match app.config.theme {
+        Theme::Dark => &DARK_PALETTE,
+        Theme::Light => &LIGHT_PALETTE,
+    }
+}
 
 pub fn ui(f: &mut Frame, app: &mut AppContext) {
     match app.input_mode {
-        InputMode::View => {
+        InputMode::View | InputMode::DeleteTaskWithSubtasks | InputMode::PickingView | InputMode::CommandLine | InputMode::Trash | InputMode::Archive => {
             let [main_area, message_area] =
                 Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
                     .margin(1)
                     .areas(f.area());
-            render_list(f, app, main_area);
+            if let InputMode::CommandLine = app.input_mode {
+                let x = message_area.x + app.input_command.width() as u16 + 1;
+                f.set_cursor_position(Position::new(x, message_area.y));
+            }
+            match details_lines(app) {
+                Some(lines) => {
+                    let [list_area, details_area] = split_details(main_area);
+                    render_list(f, app, list_area);
+                    render_details_pane(f, lines, details_area);
+                }
+                None => render_list(f, app, main_area),
+            }
             render_message_area(f, app, message_area);
         }
         InputMode::Adding | InputMode::EditingExisting => {
-            let [main_area, input_title_area, input_description_area, input_date_area, message_area] =
+            let [main_area, input_title_area, input_description_area, input_date_area, input_tags_area, input_recurrence_area, message_area] =
                 Layout::vertical([
                     Constraint::Min(1),
                     Constraint::Length(3),
+                    Constraint::Length(6),
+                    Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(1),
@@ -39,24 +90,20 @@ pub fn ui(f: &mut Frame, app: &mut AppContext) {
                 InputFieldType::Title => input_title_area,
                 InputFieldType::Description => input_description_area,
                 InputFieldType::Date => input_date_area,
+                InputFieldType::Tags => input_tags_area,
+                InputFieldType::Recurrence => input_recurrence_area,
             };
-            let x = input_area.x
-                + match app.input_field {
-                InputFieldType::Title => app.input_title.len() as u16,
-                InputFieldType::Description => app.input_description.len() as u16,
-                InputFieldType::Date => app.input_date.len() as u16,
-            }
-                + 1;
-            let y = input_area.y + 1;
-            f.set_cursor_position(Position::new(x, y));
+            f.set_cursor_position(text_cursor(input_area, app.focused_input()));
 
             render_list(f, app, main_area);
             render_input_title_area(f, app, input_title_area);
             render_input_description_area(f, app, input_description_area);
             render_input_date_area(f, app, input_date_area);
+            render_input_tags_area(f, app, input_tags_area);
+            render_input_recurrence_area(f, app, input_recurrence_area);
             render_message_area(f, app, message_area);
         }
-        InputMode::Export => {
+        InputMode::Export | InputMode::Import => {
             let [main_area, input_area, message_area] = Layout::vertical([
                 Constraint::Min(1),
                 Constraint::Length(3),
@@ -64,163 +111,651 @@ pub fn ui(f: &mut Frame, app: &mut AppContext) {
             ])
                 .margin(1)
                 .areas(f.area());
-            let x = input_area.x + app.input_export_path.len() as u16 +1;
+            f.set_cursor_position(text_cursor(input_area, input_path(app)));
+            render_list(f, app, main_area);
+            render_input_path_area(f, app, input_area);
+            render_message_area(f, app, message_area);
+        }
+        InputMode::Search | InputMode::Filter => {
+            let [main_area, input_area, message_area] = Layout::vertical([
+                Constraint::Min(1),
+                Constraint::Length(3),
+                Constraint::Length(1),
+            ])
+                .margin(1)
+                .areas(f.area());
+            let x = input_area.x + input_query(app).width() as u16;
             let y = input_area.y + 1;
             f.set_cursor_position(Position::new(x, y));
             render_list(f, app, main_area);
-            render_input_path_area(f, app, input_area);
+            render_input_query_area(f, app, input_area);
+            render_message_area(f, app, message_area);
+        }
+        InputMode::AddingProject | InputMode::AddingView => {
+            let [main_area, input_area, message_area] = Layout::vertical([
+                Constraint::Min(1),
+                Constraint::Length(3),
+                Constraint::Length(1),
+            ])
+                .margin(1)
+                .areas(f.area());
+            let x = input_area.x + input_name(app).width() as u16;
+            let y = input_area.y + 1;
+            f.set_cursor_position(Position::new(x, y));
+            render_list(f, app, main_area);
+            render_input_name_area(f, app, input_area);
             render_message_area(f, app, message_area);
         }
     }
 
+    if let InputMode::DeleteTaskWithSubtasks = app.input_mode {
+        render_delete_subtasks_popup(f, app);
+    }
+
+    if let InputMode::PickingView = app.input_mode {
+        render_view_picker_popup(f, app);
+    }
+
     if app.show_help {
         let block = Block::bordered().title("Help");
-        let area = render_popup(f.area(), 40, 80);
+        let area = render_popup(f.area(), 50, 80);
         f.render_widget(Clear, area); //this clears out the background
         f.render_widget(block, area);
 
         let popup_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
-            .constraints([Constraint::Percentage(80)])
+            .constraints([Constraint::Percentage(100)])
             .split(area);
-        let t1 = Paragraph::new(vec![
-            Line::raw("'a' to add a task"),
-            Line::raw("'m' to modify the selected task"),
-            Line::raw("'p' to change the priority"),
-            Line::raw("'s' to sort by priority"),
-            Line::raw("'t' to sort by date"),
-            Line::raw("'↑↓' to select task"),
-            Line::raw("'Space' to toggle status"),
-            Line::raw("'Ctrl + d' to delete the selected task"),
-            Line::raw("'Ctrl + e' to export the tasks to .ics file"),
-            Line::raw("'Ctrl + q' to quit"),
-        ]);
+        let t1 = Paragraph::new(help_lines(app));
         f.render_widget(t1, popup_chunks[0]);
     }
+
+    if let Some(confirmation) = &app.confirmation {
+        render_confirmation_popup(f, confirmation);
+    }
+}
+
+/// One line per action of the tasks list with the keys bound to it
+fn help_lines(app: &AppContext) -> Vec<Line<'static>> {
+    Action::ALL
+        .iter()
+        .filter_map(|action| {
+            let keys = app.config.keymap.keys(*action);
+            if keys.is_empty() {
+                return None;
+            }
+            let keys = keys.iter().map(|keys| keys.to_string()).collect::<Vec<_>>().join(", ");
+            Some(Line::from(vec![
+                Span::styled(format!("{keys:<18}"), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(action.description()),
+            ]))
+        })
+        .collect()
+}
+
+/// First key sequence bound to `action`, to mention it in a hint
+fn key_hint(app: &AppContext, action: Action) -> String {
+    app.config
+        .keymap
+        .keys(action)
+        .first()
+        .map(|keys| keys.to_string())
+        .unwrap_or_else(|| "?".to_string())
 }
 
 fn render_list(f: &mut Frame, app: &mut AppContext, area: Rect) {
+    let [tabs_area, area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(area);
+    render_project_tabs(f, app, tabs_area);
+
+    let view_title = match app.input_mode {
+        InputMode::Trash => "Trash, last deleted first ".to_string(),
+        InputMode::Archive => "Archive, last archived first ".to_string(),
+        _ => app
+            .views
+            .get(app.active_view)
+            .map(|view| {
+                let hidden = if app.hide_completed { ", completed hidden" } else { "" };
+                format!("{}, {}{hidden} ", view.name, sort_description(view.sort_by, view.sort_order))
+            })
+            .unwrap_or_default(),
+    };
     let block = Block::new()
         .title(Line::raw("Task Rustler").centered())
+        .title(Line::raw(view_title).right_aligned())
         .borders(Borders::TOP)
         .border_set(symbols::border::EMPTY)
-        .border_style(TODO_HEADER_STYLE)
-        .bg(NORMAL_ROW_BG);
+        .border_style(palette(app).header)
+        .bg(palette(app).row_bg);
 
     let items: Vec<ListItem> = app
         .task_list
         .items
         .iter()
+        .zip(app.task_list.rows.iter())
         .enumerate()
-        .map(|(_, item)| ListItem::from(item))
+        .map(|(index, (task, row))| task_list_item(task, row, app.search_hits.get(index), palette(app)))
         .collect();
 
     let list = List::new(items)
         .block(block)
-        .highlight_style(SELECTED_STYLE)
+        .highlight_style(palette(app).selected)
         .highlight_symbol(">")
         .highlight_spacing(HighlightSpacing::Always);
 
+    // the top border takes a row
+    app.task_list.page_height = area.height.saturating_sub(1) as usize;
     StatefulWidget::render(list, area, f.buffer_mut(), &mut app.task_list.state);
 }
 
+/// Terminals at least this wide show the details next to the list, narrower ones below it
+const SIDE_BY_SIDE_WIDTH: u16 = 100;
+
+/// Areas of the list and of the details of the selected task
+fn split_details(area: Rect) -> [Rect; 2] {
+    if area.width >= SIDE_BY_SIDE_WIDTH {
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area)
+    } else {
+        Layout::vertical([Constraint::Min(3), Constraint::Percentage(45)]).areas(area)
+    }
+}
+
+fn render_details_pane(f: &mut Frame, lines: Vec<Line<'static>>, area: Rect) {
+    let pane = Paragraph::new(lines)
+        .block(Block::bordered().title("Details"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(pane, area);
+}
+
+/// Every field of the selected task, its description rendered as markdown and the
+/// changes to it that can be undone. None when the details are hidden or no task is selected
+fn details_lines(app: &AppContext) -> Option<Vec<Line<'static>>> {
+    if !app.show_details {
+        return None;
+    }
+    let task = app.task_list.selected()?;
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<10}"), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(value),
+        ])
+    };
+    let none = || "-".to_string();
+    let mut lines = vec![
+        Line::from(Span::styled(task.title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+        Line::default(),
+        field("Status", if task.completed { "completed ✓" } else { "open" }.to_string()),
+        field("Priority", format!("{} {}", task.priority.name(), task.priority)),
+        field("Due", task.local_due().map(|(date, time)| format_due(date, time)).unwrap_or_else(none)),
+        field("Repeats", task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_else(none)),
+        field("Tags", if task.tags.is_empty() { none() } else { task.tags.join(" ") }),
+        field(
+            "Project",
+            app.projects.iter().find(|p| p.id == task.project_id).map(|p| p.name.clone()).unwrap_or_else(none),
+        ),
+    ];
+    if let Some(parent_id) = task.parent_id {
+        let parent = app.task_list.items.iter().find(|t| t.id == parent_id);
+        lines.push(field("Parent", parent.map(|t| t.title.clone()).unwrap_or_else(|| format!("#{parent_id}"))));
+    }
+    lines.push(field("Created", task.created_at.map(format_timestamp).unwrap_or_else(none)));
+    lines.push(field("Updated", task.updated_at.map(format_timestamp).unwrap_or_else(none)));
+    if let Some(completed_at) = task.completed_at {
+        lines.push(field("Completed", format_timestamp(completed_at)));
+    }
+    if let Some(archived_at) = task.archived_at {
+        lines.push(field("Archived", format_timestamp(archived_at)));
+    }
+    if let Some(deleted_at) = task.deleted_at {
+        lines.push(field("Deleted", format_timestamp(deleted_at)));
+    }
+    if !task.description.trim().is_empty() {
+        lines.push(Line::default());
+        lines.extend(markdown_lines(&task.description));
+    }
+    let changes = app.history.changes_of(task.id);
+    if !changes.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled("History", Style::default().add_modifier(Modifier::BOLD))));
+        lines.extend(changes.into_iter().map(|change| Line::raw(format!("• {change}"))));
+    }
+    Some(lines)
+}
+
+fn render_project_tabs(f: &mut Frame, app: &AppContext, area: Rect) {
+    let titles = app
+        .projects
+        .iter()
+        .map(|p| format!(" {} ", p.name));
+    let tabs = Tabs::new(titles)
+        .select(app.active_project)
+        .style(Style::default().fg(palette(app).text_fg))
+        .highlight_style(palette(app).header.add_modifier(Modifier::BOLD))
+        .divider(symbols::line::VERTICAL)
+        .padding("", "");
+    f.render_widget(tabs, area);
+}
+
 fn render_input_title_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
-    let input = create_input_paragraph(app, app.input_title.as_str(), "Title\u{2217}");
+    let input = create_text_input_paragraph(app, &app.input_title, "Title\u{2217}", area);
     f.render_widget(input, area);
 }
 
 fn render_input_description_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
-    let input = create_input_paragraph(app, app.input_description.as_str(), "Description");
+    let input = create_text_input_paragraph(app, &app.input_description, "Description", area);
     f.render_widget(input, area);
 }
 
 fn render_input_date_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
-    let input = create_input_paragraph(app, app.input_date.as_str(), "Date (dd-mm-yyyy)");
+    let title = format!(
+        "Date ({}, tomorrow, next fri, in 3 days, eom [HH:MM] [timezone])",
+        date_format_hint(input_date_format())
+    );
+    let input = create_text_input_paragraph(app, &app.input_date, &title, area).block(
+        Block::default()
+            .borders(Borders::BOTTOM)
+            .title(title.as_str())
+            .title_bottom(date_preview(app.input_date.value())),
+    );
     f.render_widget(input, area);
 }
 
+/// Day the date being typed resolves to, or why it can't be resolved
+fn date_preview(input: &str) -> Line<'static> {
+    if input.trim().is_empty() {
+        return Line::default();
+    }
+    match input.parse::<Due>() {
+        Ok(due) => Line::from(vec![
+            Span::raw("→ "),
+            Span::styled(format!("{} {}", due.date.format("%a"), due), Style::default().green()),
+        ]),
+        Err(e) => Line::from(Span::styled(e.to_string(), Style::default().red())),
+    }
+}
+
+fn render_input_tags_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
+    let input = create_text_input_paragraph(app, &app.input_tags, "Tags (space separated)", area);
+    f.render_widget(input, area);
+}
+
+fn render_input_recurrence_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
+    let input = create_text_input_paragraph(
+        app,
+        &app.input_recurrence,
+        "Repeat (daily, weekly mon,fri, monthly 15, every 2 weeks, after 3 days)",
+        area,
+    );
+    f.render_widget(input, area);
+}
+
+/// Path being typed in export or import mode
+fn input_path(app: &AppContext) -> &TextInput {
+    match app.input_mode {
+        InputMode::Import => &app.input_import_path,
+        _ => &app.input_export_path,
+    }
+}
+
 fn render_input_path_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
-    let input = create_input_paragraph(app, app.input_export_path.as_str(), "File path");
+    let input = create_text_input_paragraph(app, input_path(app), "File path", area);
+    f.render_widget(input, area);
+}
+
+/// Text being typed in search or filter mode
+fn input_query(app: &AppContext) -> &str {
+    match app.input_mode {
+        InputMode::Filter => app.input_filter.as_str(),
+        _ => app.input_search.as_str(),
+    }
+}
+
+fn render_input_query_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
+    let title = match app.input_mode {
+        InputMode::Filter => "Filter, e.g. priority:high status:open tag:infra due<today",
+        _ => "Search",
+    };
+    let input = create_input_paragraph(app, input_query(app), title);
+    f.render_widget(input, area);
+}
+
+/// Name being typed when creating a project or saving a view
+fn input_name(app: &AppContext) -> &str {
+    match app.input_mode {
+        InputMode::AddingView => app.input_view_name.as_str(),
+        _ => app.input_project_name.as_str(),
+    }
+}
+
+fn render_input_name_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
+    let title = match app.input_mode {
+        InputMode::AddingView => "View name",
+        _ => "Project name",
+    };
+    let input = create_input_paragraph(app, input_name(app), title);
     f.render_widget(input, area);
 }
 
 fn render_message_area(f: &mut Frame, app: &mut AppContext, area: Rect) {
     let (msg, style) = match app.input_mode {
-        InputMode::View => (
-            vec![
-                Span::styled("Tasks list", Style::default().bg(Color::White).fg(Color::Black)),
-                Span::raw("  Press "),
-                Span::styled("h", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" for help "),
-            ],
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        InputMode::Adding => (
-            if app.error.is_none() {
+        InputMode::Trash => (
+            if let Some(info) = &app.info {
                 vec![
-                    Span::styled("Add task", Style::default().bg(Color::White).fg(Color::Black)),
-                    Span::raw("  Press "),
-                    Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to stop editing, "),
-                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to add new item"),
+                    Span::styled("Trash", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw(" "),
+                    Span::styled(info.clone(), Style::default().green()),
                 ]
             } else {
-                vec![Span::styled("Error", Style::default().bg(Color::White).fg(Color::Black)),
-                     Span::raw(" "),
-                     Span::styled(
-                         app.error.clone().unwrap_or(String::new()),
-                         Style::default().red(),
-                     )]
+                let bold = Style::default().add_modifier(Modifier::BOLD);
+                vec![
+                    Span::styled("Trash", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw("  Press "),
+                    Span::styled("r", bold),
+                    Span::raw(" to restore, "),
+                    Span::styled("d", bold),
+                    Span::raw(" to delete permanently, "),
+                    Span::styled("E", bold),
+                    Span::raw(" to empty the trash, "),
+                    Span::styled("Esc", bold),
+                    Span::raw(" to go back"),
+                ]
             },
             Style::default(),
         ),
-        InputMode::EditingExisting => (
-            if app.error.is_none() {
+        InputMode::Archive => (
+            if let Some(info) = &app.info {
+                vec![
+                    Span::styled("Archive", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw(" "),
+                    Span::styled(info.clone(), Style::default().green()),
+                ]
+            } else {
+                let bold = Style::default().add_modifier(Modifier::BOLD);
                 vec![
-                    Span::styled("Edit task", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::styled("Archive", Style::default().bg(Color::White).fg(Color::Black)),
                     Span::raw("  Press "),
-                    Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to cancel, "),
-                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to save changes"),
+                    Span::styled("r", bold),
+                    Span::raw(" to take out of the archive, "),
+                    Span::styled("d", bold),
+                    Span::raw(" to delete, "),
+                    Span::styled("Esc", bold),
+                    Span::raw(" to go back"),
+                ]
+            },
+            Style::default(),
+        ),
+        InputMode::View => (
+            if let Some(info) = &app.info {
+                vec![
+                    Span::styled("Tasks list", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw(" "),
+                    Span::styled(info.clone(), Style::default().green()),
+                ]
+            } else if !app.input_search.is_empty() {
+                vec![
+                    Span::styled("Search results", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw(format!("  {:?}, press ", app.input_search)),
+                    Span::styled(key_hint(app, Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to clear the search"),
+                ]
+            } else if !app.filter.is_all() {
+                vec![
+                    Span::styled("Filtered tasks", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw(format!("  {}, press ", app.input_filter.trim())),
+                    Span::styled(key_hint(app, Action::Filter), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to change the filter, "),
+                    Span::styled(key_hint(app, Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to clear it"),
                 ]
             } else {
-                vec![Span::styled("Error", Style::default().bg(Color::White).fg(Color::Black)),
-                     Span::raw(" "),
-                     Span::styled(
-                         app.error.clone().unwrap_or(String::new()),
-                         Style::default().red(),
-                     )]
+                vec![
+                    Span::styled("Tasks list", Style::default().bg(Color::White).fg(Color::Black)),
+                    Span::raw("  Press "),
+                    Span::styled(key_hint(app, Action::ToggleHelp), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" for help, "),
+                    Span::styled(key_hint(app, Action::ToggleDetails), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to show or hide the details "),
+                ]
             },
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        InputMode::DeleteTaskWithSubtasks => (
+            vec![
+                Span::styled("Delete task", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("c", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to delete the subtasks too, "),
+                Span::styled("k", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to keep them, "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel"),
+            ],
+            Style::default(),
+        ),
+        InputMode::Adding => (
+            vec![
+                Span::styled(
+                    if app.input_parent.is_some() { "Add subtask" } else { "Add task" },
+                    Style::default().bg(Color::White).fg(Color::Black),
+                ),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to stop editing, "),
+            ]
+            .into_iter()
+            .chain(save_key_hint(app, " to add new item"))
+            .collect(),
+            Style::default(),
+        ),
+        InputMode::EditingExisting => (
+            vec![
+                Span::styled("Edit task", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel, "),
+            ]
+            .into_iter()
+            .chain(save_key_hint(app, " to save changes"))
+            .collect(),
+            Style::default(),
+        ),
+        InputMode::AddingProject => (
+            vec![
+                Span::styled("New project", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel, "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to create the project"),
+            ],
             Style::default(),
         ),
         InputMode::Export => (
-            if app.error.is_none() {
-                vec![
+            vec![
                 Span::styled("Export tasks list", Style::default().bg(Color::White).fg(Color::Black)),
                 Span::raw("  Press "),
                 Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to cancel, "),
                 Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to save file"),
-                    ]
-            } else {
-                vec![Span::styled("Error", Style::default().bg(Color::White).fg(Color::Black)),
-                     Span::raw(" "),
-                     Span::styled(
-                         app.error.clone().unwrap_or(String::new()),
-                         Style::default().red(),
-                     )]
-            },
+            ],
+            Style::default(),
+        ),
+        InputMode::Import => (
+            vec![
+                Span::styled("Import tasks", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel, "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to import the file"),
+            ],
+            Style::default(),
+        ),
+        InputMode::Search => (
+            vec![
+                Span::styled("Search", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to clear the search, "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to browse the results"),
+            ],
+            Style::default(),
+        ),
+        InputMode::PickingView => (
+            vec![
+                Span::styled("Views", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to show the view, "),
+                Span::styled("a", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to save the current filter as a view, "),
+                Span::styled("d", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to delete the view, "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel"),
+            ],
+            Style::default(),
+        ),
+        InputMode::AddingView => (
+            vec![
+                Span::styled("Save view", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel, "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to save the current filter as a view"),
+            ],
+            Style::default(),
+        ),
+        InputMode::CommandLine => (vec![Span::raw(format!(":{}", app.input_command))], Style::default()),
+        InputMode::Filter => (
+            vec![
+                Span::styled("Filter", Style::default().bg(Color::White).fg(Color::Black)),
+                Span::raw("  Press "),
+                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to clear the filter, "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to apply it"),
+            ],
             Style::default(),
         ),
     };
-    let help_message = Paragraph::new(Line::from(msg)).style(style);
+    // an error takes the place of the hints, except for the typed command and the subtasks question
+    let mut line = match (&app.error, &app.input_mode) {
+        (Some(error), mode) if !matches!(mode, InputMode::CommandLine | InputMode::DeleteTaskWithSubtasks) => {
+            error_line(error)
+        }
+        _ => Line::from(msg),
+    };
+    if let (InputMode::View, Some(pending)) = (&app.input_mode, pending_input(app)) {
+        line.push_span(Span::styled(format!("  {pending}"), Style::default().add_modifier(Modifier::BOLD)));
+    }
+    let help_message = Paragraph::new(line).style(style);
     f.render_widget(help_message, area);
 }
 
+/// The message area showing `error`
+fn error_line(error: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled("Error", Style::default().bg(Color::White).fg(Color::Black)),
+        Span::raw(" "),
+        Span::styled(error.to_string(), Style::default().red()),
+    ])
+}
+
+/// Count and keys typed so far of an action, like `5g`
+fn pending_input(app: &AppContext) -> Option<String> {
+    if app.pending_count.is_none() && app.pending_keys.is_empty() {
+        return None;
+    }
+    let count = app.pending_count.map(|count| count.to_string()).unwrap_or_default();
+    Some(format!("{count}{}", KeySequence(app.pending_keys.clone())))
+}
+
+fn render_delete_subtasks_popup(f: &mut Frame, app: &AppContext) {
+    let subtasks = app
+        .task_list
+        .state
+        .selected()
+        .and_then(|index| app.task_list.rows.get(index))
+        .map(|row| row.subtasks)
+        .unwrap_or_default();
+    let block = Block::bordered().title("Delete task");
+    let area = render_popup(f.area(), 50, 30);
+    f.render_widget(Clear, area);
+    let text = Paragraph::new(vec![
+        Line::raw(format!("The task has {subtasks} subtasks")),
+        Line::raw(""),
+        Line::raw("'c' to delete the task and its subtasks"),
+        Line::raw("'k' to delete the task and keep its subtasks"),
+        Line::raw("'Esc' to cancel"),
+    ])
+    .block(block);
+    f.render_widget(text, area);
+}
+
+/// Dialog asking to confirm a command before executing it
+fn render_confirmation_popup(f: &mut Frame, confirmation: &Confirmation) {
+    let block = Block::bordered().title("Confirm").border_style(Style::default().yellow());
+    let area = render_popup(f.area(), 50, 30);
+    f.render_widget(Clear, area);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let text = Paragraph::new(vec![
+        Line::raw(confirmation.message.clone()),
+        Line::raw(""),
+        Line::from(vec![
+            Span::styled("y", bold),
+            Span::raw(" or "),
+            Span::styled("Enter", bold),
+            Span::raw(" to confirm, "),
+            Span::styled("n", bold),
+            Span::raw(" or "),
+            Span::styled("Esc", bold),
+            Span::raw(" to cancel"),
+        ]),
+    ])
+    .wrap(Wrap { trim: false })
+    .block(block);
+    f.render_widget(text, area);
+}
+
+fn render_view_picker_popup(f: &mut Frame, app: &mut AppContext) {
+    let block = Block::bordered().title("Views");
+    let area = render_popup(f.area(), 60, 50);
+    f.render_widget(Clear, area);
+    let items: Vec<ListItem> = app
+        .views
+        .iter()
+        .map(|view| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ", view.name)),
+                Span::styled(
+                    format!("{} {}", view.query, sort_description(view.sort_by, view.sort_order)),
+                    Style::default().fg(palette(app).completed_text_fg),
+                ),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(palette(app).selected)
+        .highlight_symbol(">")
+        .highlight_spacing(HighlightSpacing::Always);
+    StatefulWidget::render(list, area, f.buffer_mut(), &mut app.view_picker);
+}
+
+fn sort_description(sort_by: SortBy, sort_order: SortOrder) -> &'static str {
+    match (sort_by, sort_order) {
+        (SortBy::Priority, SortOrder::High) => "by highest priority",
+        (SortBy::Priority, SortOrder::Low) => "by lowest priority",
+        (SortBy::Date, SortOrder::High) => "by newest date",
+        (SortBy::Date, SortOrder::Low) => "by oldest date",
+    }
+}
+
 fn render_popup(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
@@ -231,56 +766,120 @@ fn render_popup(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
 
 impl From<&Task> for ListItem<'_> {
     fn from(value: &Task) -> Self {
-        let todo_line = vec![
-            Span::styled(" ☐", Style::default().fg(TEXT_FG_COLOR)),
-            Span::styled(
-                format!(" ({})", value.priority),
-                Style::default().fg(priority_to_color(&value.priority)),
-            ),
-            Span::styled(
-                format!(
-                    "{:>14}",
-                    value
-                        .date
-                        .clone()
-                        .try_into()
-                        .unwrap_or(format!("{}", " ".repeat(10)))
-                ),
-                Style::default().fg(TEXT_FG_COLOR),
-            ),
-            Span::styled(
-                format!("    {} - {}", value.title, value.description),
-                Style::default().fg(TEXT_FG_COLOR),
-            ),
-        ];
-        let done_line = vec![
-            Span::styled(" ✓", Style::default().fg(COMPLETED_TEXT_FG_COLOR)),
-            Span::styled(
-                format!(" ({})", value.priority),
-                Style::default().fg(priority_to_color(&value.priority)),
-            ),
-            Span::styled(
-                format!(
-                    "{:>14}",
-                    value
-                        .date
-                        .clone()
-                        .try_into()
-                        .unwrap_or(format!("{}", " ".repeat(10)))
-                ),
-                Style::default().fg(COMPLETED_TEXT_FG_COLOR),
-            ),
-            Span::styled(
-                format!("    {} - {}", value.title, value.description),
-                Style::default().fg(COMPLETED_TEXT_FG_COLOR),
-            ),
-        ];
-        let line: Line = match value.completed {
-            false => todo_line.into(),
-            true => done_line.into(),
+        task_list_item(value, &TreeRow::default(), None, &DARK_PALETTE)
+    }
+}
+
+/// Title and description of a task as spans, with the terms matched by a search highlighted
+fn task_text_spans(value: &Task, hit: Option<&SearchHit>, tree_prefix: &str, progress: &str, style: Style) -> Vec<Span<'static>> {
+    let Some(hit) = hit else {
+        return vec![Span::styled(
+            format!("    {}{} - {}{}", tree_prefix, value.title, first_line(&value.description), progress),
+            style,
+        )];
+    };
+    let match_style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = vec![Span::styled(format!("    {tree_prefix}"), style)];
+    let description = if hit.snippet.is_empty() { first_line(&value.description) } else { hit.snippet.replace('\n', " ") };
+    for (text, matched) in highlighted_parts(&hit.title) {
+        spans.push(Span::styled(text.to_string(), if matched { match_style } else { style }));
+    }
+    spans.push(Span::styled(" - ", style));
+    for (text, matched) in highlighted_parts(&description) {
+        spans.push(Span::styled(text.to_string(), if matched { match_style } else { style }));
+    }
+    spans.push(Span::styled(progress.to_string(), style));
+    spans
+}
+
+/// First line of a description, the rest is shown in the description pane
+fn first_line(description: &str) -> String {
+    let mut lines = description.trim().lines();
+    let first = lines.next().unwrap_or_default();
+    if lines.next().is_some() { format!("{first} …") } else { first.to_string() }
+}
+
+/// Day and time the task is due, in the local timezone
+fn due_text(task: &Task) -> String {
+    task.local_due()
+        .map(|(date, time)| format_due(date, time))
+        .unwrap_or_default()
+}
+
+/// Build the list row of a task, indented by its depth in the tree of subtasks.
+/// Tasks with subtasks show whether they are collapsed and the completed subtasks
+fn task_list_item<'a>(value: &Task, row: &TreeRow, hit: Option<&SearchHit>, palette: &Palette) -> ListItem<'a> {
+    let mut tree_prefix = "  ".repeat(row.depth);
+    let mut progress = String::new();
+    if row.subtasks > 0 {
+        tree_prefix.push_str(if row.collapsed { "▸ " } else { "▾ " });
+        progress = format!(" [{}/{}]", row.completed_subtasks, row.subtasks);
+    }
+    if value.recurrence.is_some() {
+        progress.push_str(" ↻");
+    }
+    let mut todo_line = vec![
+        Span::styled(" ☐", Style::default().fg(palette.text_fg)),
+        Span::styled(
+            format!(" ({})", value.priority),
+            Style::default().fg(priority_to_color(&value.priority)),
+        ),
+        Span::styled(
+            format!("    {:<16}", due_text(value)),
+            Style::default().fg(palette.text_fg),
+        ),
+    ];
+    todo_line.extend(task_text_spans(value, hit, &tree_prefix, &progress, Style::default().fg(palette.text_fg)));
+    let mut done_line = vec![
+        Span::styled(" ✓", Style::default().fg(palette.completed_text_fg)),
+        Span::styled(
+            format!(" ({})", value.priority),
+            Style::default().fg(priority_to_color(&value.priority)),
+        ),
+        Span::styled(
+            format!("    {:<16}", due_text(value)),
+            Style::default().fg(palette.completed_text_fg),
+        ),
+    ];
+    done_line.extend(task_text_spans(value, hit, &tree_prefix, &progress, Style::default().fg(palette.completed_text_fg)));
+    todo_line.extend(tag_chips(&value.tags, false, palette));
+    done_line.extend(tag_chips(&value.tags, true, palette));
+    let line: Line = match value.completed {
+        false => todo_line.into(),
+        true => done_line.into(),
+    };
+    ListItem::new(line)
+}
+
+/// Render tags as chips with a background color derived from the tag name,
+/// so the same tag always gets the same color
+fn tag_chips(tags: &[String], completed: bool, palette: &Palette) -> Vec<Span<'static>> {
+    let mut chips = vec![];
+    for tag in tags {
+        let style = if completed {
+            Style::default().fg(palette.completed_text_fg).bg(palette.completed_tag_bg)
+        } else {
+            Style::default().fg(SLATE.c950).bg(tag_to_color(tag))
         };
-        ListItem::new(line)
+        chips.push(Span::raw(" "));
+        chips.push(Span::styled(format!(" {tag} "), style));
     }
+    chips
+}
+
+fn tag_to_color(tag: &str) -> Color {
+    const TAG_COLORS: [Color; 6] = [
+        Color::LightBlue,
+        Color::LightCyan,
+        Color::LightGreen,
+        Color::LightMagenta,
+        Color::LightYellow,
+        Color::LightRed,
+    ];
+    let hash = tag
+        .bytes()
+        .fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    TAG_COLORS[hash % TAG_COLORS.len()]
 }
 
 fn priority_to_color(priority: &Priority) -> Color {
@@ -294,9 +893,44 @@ fn priority_to_color(priority: &Priority) -> Color {
 fn create_input_paragraph<'a>(app: &'a AppContext, text: &'a str, title: &'a str) -> Paragraph<'a> {
     Paragraph::new(text)
         .style(match app.input_mode {
-            InputMode::View | InputMode::Export => Style::default(),
+            InputMode::View | InputMode::Export | InputMode::Import | InputMode::DeleteTaskWithSubtasks | InputMode::PickingView | InputMode::CommandLine | InputMode::Trash | InputMode::Archive => Style::default(),
+            InputMode::AddingProject | InputMode::AddingView => Style::default().fg(Color::Cyan),
+            InputMode::Search => Style::default().fg(Color::Magenta),
+            InputMode::Filter => Style::default().fg(Color::Blue),
             InputMode::Adding => Style::default().fg(Color::Green),
             InputMode::EditingExisting => Style::default().fg(Color::Yellow),
         })
         .block(Block::default().borders(Borders::BOTTOM).title(title))
 }
+
+/// Paragraph of a text input in a field of `area`, scrolled to keep the cursor visible
+fn create_text_input_paragraph<'a>(app: &'a AppContext, input: &'a TextInput, title: &'a str, area: Rect) -> Paragraph<'a> {
+    create_input_paragraph(app, input.value(), title)
+        .scroll((input.scroll_rows(text_rows(area)), input.scroll_offset(area.width)))
+}
+
+/// Lines of text a field of `area` shows, between its title and bottom border
+fn text_rows(area: Rect) -> u16 {
+    area.height.saturating_sub(2)
+}
+
+/// Position of the cursor of a text input in the field of `area`, below its title
+fn text_cursor(area: Rect, input: &TextInput) -> Position {
+    let x = input.cursor_width() as u16 - input.scroll_offset(area.width);
+    let y = input.cursor_row() as u16 - input.scroll_rows(text_rows(area));
+    Position::new(area.x + x, area.y + 1 + y)
+}
+
+/// Key that saves the task, Enter adds a line to the description instead
+fn save_key_hint(app: &AppContext, action: &'static str) -> Vec<Span<'static>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    match app.input_field {
+        InputFieldType::Description => vec![
+            Span::styled("Enter", bold),
+            Span::raw(" for a new line, "),
+            Span::styled("Ctrl + s", bold),
+            Span::raw(action),
+        ],
+        _ => vec![Span::styled("Enter", bold), Span::raw(action)],
+    }
+}
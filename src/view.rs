@@ -0,0 +1,33 @@
+use crate::query::{ParseQueryError, Query};
+use crate::task_manager::{SortBy, SortOrder};
+
+/// Id of the view showing every task, it can't be deleted
+pub const DEFAULT_VIEW_ID: i32 = 1;
+
+/// A named filter with its sort order, e.g. "Overdue" or "High priority open"
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct View {
+    pub id: i32,
+    pub name: String,
+    /// Filter in the syntax of `Query`
+    pub query: String,
+    pub sort_by: SortBy,
+    pub sort_order: SortOrder,
+}
+
+impl View {
+    pub fn new(name: &str, query: &str, sort_by: SortBy, sort_order: SortOrder) -> Self {
+        Self {
+            id: 0,
+            name: name.trim().to_string(),
+            query: query.trim().to_string(),
+            sort_by,
+            sort_order,
+        }
+    }
+
+    /// Parse the filter of the view
+    pub fn parse_query(&self) -> Result<Query, ParseQueryError> {
+        self.query.parse()
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(test)]
+mod test {
+    use task_rustler::cli::{parse_args, parse_global_options, run, AddArgs, CliCommand, GlobalOptions};
+    use task_rustler::config::Config;
+    use task_rustler::query::{Query, Term};
+    use task_rustler::task::Priority;
+    use task_rustler::task_manager::TasksService;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn run_args(service: &TasksService, values: &[&str]) -> String {
+        let command = parse_args(&args(values)).unwrap().unwrap();
+        let mut out = vec![];
+        run(command, service, &Config::default(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_subcommands() {
+        assert_eq!(parse_args(&[]).unwrap(), None);
+        assert_eq!(
+            parse_args(&args(&["add", "buy milk", "--due", "20-10-2026", "--priority", "high"]))
+                .unwrap(),
+            Some(CliCommand::Add(AddArgs {
+                title: String::from("buy milk"),
+                due: Some(String::from("20-10-2026")),
+                priority: Some(String::from("high")),
+                ..AddArgs::default()
+            }))
+        );
+        assert_eq!(
+            parse_args(&args(&["list", "--json"])).unwrap(),
+            Some(CliCommand::List { project: None, view: None, filter: Query::all(), json: true })
+        );
+        assert_eq!(
+            parse_args(&args(&["list", "--filter", "#home"])).unwrap(),
+            Some(CliCommand::List {
+                project: None,
+                view: None,
+                filter: Query::Term(Term::Tag(String::from("home"))),
+                json: false,
+            })
+        );
+        assert!(parse_args(&args(&["list", "--filter", "colour:red"])).is_err());
+        assert_eq!(parse_args(&args(&["rm", "3"])).unwrap(), Some(CliCommand::Remove { id: 3 }));
+        assert_eq!(parse_args(&args(&["trash", "restore", "3"])).unwrap(), Some(CliCommand::TrashRestore { id: 3 }));
+        assert_eq!(parse_args(&args(&["trash", "list", "--json"])).unwrap(), Some(CliCommand::TrashList { json: true }));
+        assert!(parse_args(&args(&["trash"])).is_err());
+        assert!(parse_args(&args(&["trash", "empty", "now"])).is_err());
+        assert!(parse_args(&args(&["done", "x"])).is_err());
+        assert!(parse_args(&args(&["add", "--due"])).is_err());
+        assert!(parse_args(&args(&["frobnicate"])).is_err());
+    }
+
+    #[test]
+    fn parse_global_options_before_the_command() {
+        let values = args(&["--db", "work.db", "--config", "alt.toml", "list", "--json"]);
+        let (options, rest) = parse_global_options(&values).unwrap();
+        assert_eq!(
+            options,
+            GlobalOptions { db: Some("work.db".into()), config: Some("alt.toml".into()) }
+        );
+        assert_eq!(rest, &values[4..]);
+        let values = args(&["add", "--db", "x"]);
+        assert_eq!(parse_global_options(&values).unwrap(), (GlobalOptions::default(), &values[..]));
+        assert!(parse_global_options(&args(&["--db"])).is_err());
+    }
+
+    #[test]
+    fn config_defaults_apply_to_add_and_list() {
+        let service = TasksService::default();
+        let config = Config::parse("default_priority = \"medium\"\ndefault_sort = \"date\"\ndefault_sort_order = \"low\"").unwrap();
+        let run_with_config = |values: &[&str]| {
+            let mut out = vec![];
+            run(parse_args(&args(values)).unwrap().unwrap(), &service, &config, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        run_with_config(&["add", "later", "--due", "01-01-2030"]);
+        run_with_config(&["add", "sooner", "--due", "01-01-2029", "--priority", "high"]);
+        let json: serde_json::Value = serde_json::from_str(&run_with_config(&["list", "--json"])).unwrap();
+        assert_eq!(json[0]["title"], "sooner");
+        assert_eq!(json[1]["title"], "later");
+        assert_eq!(json[1]["priority"], "medium");
+    }
+
+    #[test]
+    fn add_done_list_and_remove() {
+        let service = TasksService::default();
+        let id: i32 = run_args(
+            &service,
+            &["add", "buy milk", "--due", "20-10-2026", "--priority", "high", "--tags", "home"],
+        )
+        .trim()
+        .parse()
+        .unwrap();
+        let task = service.get_task(id).unwrap().unwrap();
+        assert_eq!(task.title, "buy milk");
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.tags, vec!["home"]);
+
+        run_args(&service, &["done", &id.to_string()]);
+        assert!(service.get_task(id).unwrap().unwrap().completed);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&run_args(&service, &["list", "--json"])).unwrap();
+        assert_eq!(json[0]["id"], id);
+        assert_eq!(json[0]["due"], "20-10-2026");
+        assert_eq!(json[0]["completed"], true);
+        assert_eq!(json[0]["project"], "Inbox");
+
+        run_args(&service, &["rm", &id.to_string()]);
+        assert!(service.is_empty().unwrap());
+        let command = parse_args(&args(&["rm", &id.to_string()])).unwrap().unwrap();
+        assert!(run(command, &service, &Config::default(), &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn restore_and_empty_the_trash() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "buy milk"]);
+        run_args(&service, &["add", "call mum"]);
+        run_args(&service, &["rm", "1"]);
+        run_args(&service, &["rm", "2"]);
+        let trash = run_args(&service, &["trash", "list"]);
+        assert_eq!(trash.lines().count(), 2);
+        assert!(trash.lines().all(|line| line.contains("(deleted ")));
+        let json: serde_json::Value = serde_json::from_str(&run_args(&service, &["trash", "list", "--json"])).unwrap();
+        assert!(json[0]["deleted_at"].is_string());
+
+        run_args(&service, &["trash", "restore", "1"]);
+        assert_eq!(service.get_task(1).unwrap().unwrap().title, "buy milk");
+        let command = parse_args(&args(&["trash", "restore", "1"])).unwrap().unwrap();
+        assert!(run(command, &service, &Config::default(), &mut vec![]).is_err());
+
+        assert_eq!(run_args(&service, &["trash", "empty"]), "Permanently deleted 1 tasks\n");
+        assert_eq!(run_args(&service, &["trash", "list"]), "");
+        assert_eq!(service.length().unwrap(), 1);
+    }
+
+    #[test]
+    fn add_with_due_time() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "standup", "--due", "20-10-2026 09:30 Europe/Rome"]);
+        let json: serde_json::Value =
+            serde_json::from_str(&run_args(&service, &["list", "--json"])).unwrap();
+        assert_eq!(json[0]["due"], "20-10-2026");
+        assert_eq!(json[0]["due_time"], "09:30");
+        assert_eq!(json[0]["timezone"], "Europe/Rome");
+        assert_eq!(json[0]["due_at"], "2026-10-20T09:30:00+02:00");
+        let command = parse_args(&args(&["add", "standup", "--due", "20-10-2026 09:30 Nowhere"])).unwrap().unwrap();
+        assert!(run(command, &service, &Config::default(), &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn list_with_filter() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "fix the router", "--priority", "high", "--tags", "infra"]);
+        run_args(&service, &["add", "buy milk", "--tags", "home"]);
+        run_args(&service, &["add", "renew certificates", "--tags", "infra", "--due", "20-10-2026"]);
+        let listed = run_args(&service, &["list", "--filter", "tag:infra due:none"]);
+        assert_eq!(listed.lines().count(), 1);
+        assert!(listed.contains("fix the router"));
+        let listed = run_args(&service, &["list", "--project", "inbox", "--filter", "milk or due<2026-11-01"]);
+        assert_eq!(listed.lines().count(), 2);
+        assert!(!listed.contains("fix the router"));
+    }
+
+    #[test]
+    fn list_saved_view() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "pay rent", "--due", "01-01-2020"]);
+        run_args(&service, &["add", "renew passport", "--due", "01-03-2020", "--tags", "home"]);
+        run_args(&service, &["add", "plan holidays", "--due", "01-01-2100"]);
+        let listed = run_args(&service, &["list", "--view", "overdue"]);
+        let titles: Vec<&str> = listed.lines().map(|l| l.split_once("2020 ").unwrap().1).collect();
+        assert_eq!(titles, vec!["pay rent", "renew passport #home"]);
+        let listed = run_args(&service, &["list", "--view", "Overdue", "--filter", "#home"]);
+        assert_eq!(listed.lines().count(), 1);
+
+        let command = parse_args(&args(&["list", "--view", "someday"])).unwrap().unwrap();
+        let err = run(command, &service, &Config::default(), &mut vec![]).unwrap_err();
+        assert!(err.to_string().contains("Overdue"));
+    }
+
+    #[test]
+    fn export_to_stdout() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "write report"]);
+        let calendar = run_args(&service, &["export"]);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR"));
+        assert!(calendar.contains("SUMMARY:write report"));
+    }
+
+    #[test]
+    fn import_counts_new_and_updated_tasks() {
+        let service = TasksService::default();
+        run_args(&service, &["add", "write report"]);
+        let path = std::env::temp_dir().join(format!("task_rustler_cli_import_{}.ics", std::process::id()));
+        std::fs::write(&path, run_args(&service, &["export"])).unwrap();
+        let other = TasksService::default();
+        let path = path.display().to_string();
+
+        assert_eq!(run_args(&other, &["import", &path]), "Imported 1 new tasks, 0 updated\n");
+        assert_eq!(run_args(&service, &["import", &path]), "Imported 0 new tasks, 1 updated\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
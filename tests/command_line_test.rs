@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod test {
+    use task_rustler::command_line::LineCommand;
+    use task_rustler::task_manager::{SortBy, SortOrder};
+
+    fn parse(input: &str) -> LineCommand {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse("sort date"), LineCommand::Sort(SortBy::Date, None));
+        assert_eq!(parse(":sort priority low"), LineCommand::Sort(SortBy::Priority, Some(SortOrder::Low)));
+        assert_eq!(parse("sort Due HIGH"), LineCommand::Sort(SortBy::Date, Some(SortOrder::High)));
+        assert_eq!(parse("export"), LineCommand::Export(None));
+        assert_eq!(parse("export ~/tasks.ics"), LineCommand::Export(Some("~/tasks.ics".to_string())));
+        assert_eq!(parse("w /tmp/my tasks.ics"), LineCommand::Export(Some("/tmp/my tasks.ics".to_string())));
+        assert_eq!(parse("w"), LineCommand::Write);
+        assert_eq!(parse("import tasks.ics"), LineCommand::Import("tasks.ics".to_string()));
+        assert_eq!(parse("filter tag:infra -status:done"), LineCommand::Filter("tag:infra -status:done".to_string()));
+        assert_eq!(parse("filter"), LineCommand::Filter(String::new()));
+        assert_eq!(parse("view Overdue"), LineCommand::View("Overdue".to_string()));
+        assert_eq!(parse("project side projects"), LineCommand::Project("side projects".to_string()));
+        assert_eq!(parse("12"), LineCommand::Goto(12));
+        assert_eq!(parse("delete"), LineCommand::Delete);
+        assert_eq!(parse("clear"), LineCommand::Clear);
+        assert_eq!(parse("trash"), LineCommand::Trash);
+        assert_eq!(parse("archive"), LineCommand::Archive);
+        assert_eq!(parse("u"), LineCommand::Undo);
+        assert_eq!(parse("redo"), LineCommand::Redo);
+        assert_eq!(parse("help"), LineCommand::Help);
+        for quit in ["q", "q!", "quit", "wq", "x"] {
+            assert_eq!(parse(quit), LineCommand::Quit);
+        }
+    }
+
+    #[test]
+    fn report_invalid_commands() {
+        assert!("sort".parse::<LineCommand>().is_err());
+        assert!("sort size".parse::<LineCommand>().is_err());
+        assert!("sort date sideways".parse::<LineCommand>().is_err());
+        assert!("import".parse::<LineCommand>().is_err());
+        assert!("view".parse::<LineCommand>().is_err());
+        assert!("".parse::<LineCommand>().is_err());
+        let err = "q now".parse::<LineCommand>().unwrap_err();
+        assert_eq!(err.to_string(), "Invalid command: :q takes no argument");
+        let err = "frobnicate".parse::<LineCommand>().unwrap_err();
+        assert_eq!(err.to_string(), "Invalid command: unknown command :frobnicate");
+    }
+}
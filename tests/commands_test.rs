@@ -1,27 +1,516 @@
 #[cfg(test)]
 mod test {
-    use task_rustler::app::AppContext;
-    use task_rustler::command::{AddTaskCommand, Command};
+    use task_rustler::app::{AppContext, InputMode};
+    use task_rustler::command::{
+        execute_or_confirm, AddTaskCommand, ArchiveTaskCommand, CancelConfirmationCommand, Command, ConfirmCommand, DeleteArchivedTaskCommand, EmptyTrashCommand,
+        ExitTrashCommand, FinishingExportCommand, FinishingImportCommand, PurgeTaskCommand, RestoreTaskCommand,
+        RunCommandLineCommand, DeleteTaskAndSubtasksCommand, DeleteTaskCommand, DeleteTaskKeepingSubtasksCommand, DeleteTasksCommand,
+        AddViewCommand, EnterAddSubtaskModeCommand, ExitFilterCommand, ExitSearchCommand, FinishFilterCommand, RedoCommand,
+        SortByDateCommand, ToggleCompletedCommand, ToggleItemPriorityCommand, ToggleTaskStatusCommand, UndoCommand,
+        EnterArchiveCommand, UnarchiveTaskCommand, UpdateSearchCommand,
+    };
+    use task_rustler::config::Config;
+    use task_rustler::task::{Priority, Task};
+    use task_rustler::text_input::TextInput;
+    use task_rustler::task_manager::{SortBy, SortOrder};
 
     #[test]
     fn add_task_command_test_wrong_date_format() {
-        let mut app = AppContext::new(String::new());
-        app.input_title = String::from("test title");
-        app.input_description = String::from("test description");
-        app.input_date = String::from("invalid date");
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("test title");
+        app.input_description = TextInput::from("test description");
+        app.input_date = TextInput::from("invalid date");
         let res = AddTaskCommand.execute(&mut app);
         assert!(res.is_err());
-        assert_eq!(res.err().unwrap().to_string(), "Invalid date format, use dd-mm-yyyy")
+        assert_eq!(res.err().unwrap().to_string(), "Invalid date, use dd-mm-yyyy or words like tomorrow or next fri, optionally followed by HH:MM and a timezone")
     }
 
     #[test]
     fn add_task_command_test_empty_title() {
-        let mut app = AppContext::new(String::new());
-        app.input_title = String::from("");
-        app.input_description = String::from("test description");
-        app.input_date = String::from("10-12-2012");
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("");
+        app.input_description = TextInput::from("test description");
+        app.input_date = TextInput::from("10-12-2012");
         let res = AddTaskCommand.execute(&mut app);
         assert!(res.is_err());
         assert_eq!(res.err().unwrap().to_string(), "You must insert at least a title for the task");
     }
+
+    #[test]
+    fn add_subtask_and_delete_parent_keeping_it() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("parent");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        EnterAddSubtaskModeCommand.execute(&mut app).unwrap();
+        app.input_title = TextInput::from("child");
+        AddTaskCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+        assert_eq!(app.task_list.rows[0].subtasks, 1);
+        assert_eq!(app.task_list.rows[1].depth, 1);
+
+        app.collapse_selected().unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        app.expand_selected().unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+
+        DeleteTaskCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::DeleteTaskWithSubtasks));
+        DeleteTaskKeepingSubtasksCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        assert_eq!(app.task_list.items[0].title, "child");
+        assert_eq!(app.task_list.rows[0].depth, 0);
+    }
+
+    #[test]
+    fn undo_and_redo_add_priority_and_status() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        assert_eq!(UndoCommand.execute(&mut app).unwrap_err().to_string(), "Nothing to undo");
+        app.input_title = TextInput::from("task");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        ToggleItemPriorityCommand.execute(&mut app).unwrap();
+        ToggleTaskStatusCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items[0].completed);
+
+        UndoCommand.execute(&mut app).unwrap();
+        assert!(!app.task_list.items[0].completed);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items[0].priority, Priority::Low);
+        UndoCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+        // undoing the creation removes the task, it doesn't go to the trash
+        assert!(app.tasks_service.get_trash().unwrap().is_empty());
+
+        RedoCommand.execute(&mut app).unwrap();
+        RedoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        assert_ne!(app.task_list.items[0].priority, Priority::Low);
+        assert!(!app.task_list.items[0].completed);
+    }
+
+    #[test]
+    fn history_of_a_task() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("first");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.input_title = TextInput::from("second");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        let toggled = app.task_list.items[0].id;
+        let other = app.task_list.items[1].id;
+        ToggleTaskStatusCommand.execute(&mut app).unwrap();
+
+        assert_eq!(app.history.changes_of(toggled), vec!["toggle task status", "add task"]);
+        assert_eq!(app.history.changes_of(other), vec!["add task"]);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.history.changes_of(toggled), vec!["add task"]);
+    }
+
+    #[test]
+    fn undo_delete_restores_subtasks() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("parent");
+        app.input_tags = TextInput::from("work");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        EnterAddSubtaskModeCommand.execute(&mut app).unwrap();
+        app.input_title = TextInput::from("child");
+        AddTaskCommand.execute(&mut app).unwrap();
+
+        app.task_list.state.select(Some(0));
+        DeleteTaskCommand.execute(&mut app).unwrap();
+        DeleteTaskKeepingSubtasksCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+        assert_eq!(app.task_list.items[0].tags, vec!["work"]);
+        assert_eq!(app.task_list.rows[1].depth, 1);
+
+        app.task_list.state.select(Some(0));
+        DeleteTaskCommand.execute(&mut app).unwrap();
+        DeleteTaskAndSubtasksCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+        assert_eq!(app.task_list.items[1].parent_id, Some(app.task_list.items[0].id));
+        RedoCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+        assert_eq!(app.tasks_service.get_trash().unwrap().len(), 2);
+        assert_eq!(RedoCommand.execute(&mut app).unwrap_err().to_string(), "Nothing to redo");
+    }
+
+    #[test]
+    fn undo_completing_a_recurring_task_removes_the_next_occurrence() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("water the plants");
+        app.input_date = TextInput::from("20-10-2026");
+        app.input_recurrence = TextInput::from("daily");
+        AddTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        ToggleTaskStatusCommand.execute(&mut app).unwrap();
+        assert_eq!(app.tasks_service.length().unwrap(), 2);
+
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.tasks_service.length().unwrap(), 1);
+        assert!(!app.task_list.items[0].completed);
+        assert!(app.tasks_service.get_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_filters_the_list_while_typing() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["write report", "review report", "water plants"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.input_search = String::from("rep");
+        UpdateSearchCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+        assert_eq!(app.search_hits.len(), 2);
+        assert_eq!(app.task_list.state.selected(), Some(0));
+
+        app.input_search = String::from("rev rep");
+        UpdateSearchCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items[0].title, "review report");
+
+        ExitSearchCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 3);
+        assert!(app.search_hits.is_empty());
+    }
+
+    #[test]
+    fn filter_shows_only_matching_tasks() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["write report", "water plants"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.select_first();
+        ToggleItemPriorityCommand.execute(&mut app).unwrap();
+        ToggleItemPriorityCommand.execute(&mut app).unwrap();
+
+        app.input_filter = String::from("priority:high");
+        FinishFilterCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::View));
+        assert_eq!(app.task_list.items.len(), 1);
+        assert_eq!(app.task_list.items[0].priority, Priority::High);
+
+        app.input_filter = String::from("priority:urgent");
+        assert!(FinishFilterCommand.execute(&mut app).is_err());
+        assert_eq!(app.task_list.items.len(), 1);
+
+        ExitFilterCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+    }
+
+    #[test]
+    fn save_filter_as_view_with_its_own_sort() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["write report", "water plants"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        let views = app.views.len();
+        app.input_filter = String::from("water");
+        app.input_view_name = String::from("Garden");
+        AddViewCommand.execute(&mut app).unwrap();
+        assert_eq!(app.views.len(), views + 1);
+        assert_eq!(app.views[app.active_view].name, "Garden");
+        assert!(app.filter.is_all());
+        assert_eq!(app.task_list.items.len(), 1);
+
+        SortByDateCommand.execute(&mut app).unwrap();
+        let saved = app.tasks_service.get_all_views().unwrap();
+        assert_eq!(saved[app.active_view].sort_by, SortBy::Date);
+        // other views keep their own sort
+        assert_eq!(saved[0].sort_by, SortBy::Priority);
+
+        app.input_view_name = String::from("Garden");
+        assert!(AddViewCommand.execute(&mut app).is_err());
+        app.input_view_name = String::from("GARDEN");
+        assert_eq!(AddViewCommand.execute(&mut app).unwrap_err().to_string(), "A view named GARDEN already exists");
+    }
+
+    #[test]
+    fn configured_sort_applies_to_the_default_view() {
+        let config = Config {
+            database: "".into(),
+            default_sort: SortBy::Date,
+            default_sort_order: SortOrder::Low,
+            ..Config::default()
+        };
+        let mut app = AppContext::with_config(config).unwrap();
+        for (title, date) in [("later", "01-01-2030"), ("sooner", "01-01-2029")] {
+            app.input_title = TextInput::from(title);
+            app.input_date = TextInput::from(date);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        assert_eq!(app.views[0].sort_by, SortBy::Date);
+        assert_eq!(app.views[0].sort_order, SortOrder::Low);
+        assert_eq!(app.task_list.items[0].title, "sooner");
+    }
+
+    #[test]
+    fn run_typed_commands() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["write report", "water plants", "wash car"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.input_command = String::from("sort date low");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::View));
+        assert!(app.input_command.is_empty());
+        assert_eq!(app.views[app.active_view].sort_by, SortBy::Date);
+        assert_eq!(app.views[app.active_view].sort_order, SortOrder::Low);
+
+        app.input_command = String::from("3");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.state.selected(), Some(2));
+        app.input_command = String::from("99");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.state.selected(), Some(2));
+
+        app.input_command = String::from("filter wa");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+
+        app.input_command = String::from("w");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(app.info.is_some());
+        assert!(!app.quit);
+
+        app.input_command = String::from("project nowhere");
+        let err = RunCommandLineCommand.execute(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "No project named nowhere");
+        app.input_command = String::from("frobnicate");
+        let err = RunCommandLineCommand.execute(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid command: unknown command :frobnicate");
+
+        app.input_command = String::from("wq");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn confirm_destructive_commands() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["first", "second", "third"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.task_list.state.select(Some(0));
+        let title = app.task_list.items[0].title.clone();
+        execute_or_confirm(DeleteTaskCommand, &mut app).unwrap();
+        assert_eq!(app.confirmation.as_ref().unwrap().message, format!("Delete \"{title}\"?"));
+        CancelConfirmationCommand.execute(&mut app).unwrap();
+        assert!(app.confirmation.is_none());
+        assert_eq!(app.task_list.items.len(), 3);
+        execute_or_confirm(DeleteTaskCommand, &mut app).unwrap();
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert!(app.confirmation.is_none());
+        assert_eq!(app.task_list.items.len(), 2);
+
+        app.input_command = String::from("delete");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert_eq!(app.confirmation.as_ref().unwrap().message, "Delete the 2 tasks of the list and their subtasks?");
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+
+        app.config.confirm = false;
+        app.input_command = String::from("clear");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(app.confirmation.is_none());
+        assert!(app.tasks_service.is_empty().unwrap());
+        assert_eq!(app.tasks_service.get_trash().unwrap().len(), 3);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.tasks_service.length().unwrap(), 2);
+        assert_eq!(app.tasks_service.get_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn confirm_overwriting_an_export() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("task");
+        AddTaskCommand.execute(&mut app).unwrap();
+        let path = std::env::temp_dir().join(format!("task_rustler_overwrite_{}.ics", std::process::id()));
+        std::fs::write(&path, "previous export").unwrap();
+        app.input_export_path = TextInput::from(path.display().to_string());
+        app.input_mode = InputMode::Export;
+
+        execute_or_confirm(FinishingExportCommand, &mut app).unwrap();
+        assert_eq!(app.confirmation.as_ref().unwrap().message, format!("Overwrite {}?", path.display()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous export");
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("BEGIN:VCALENDAR"));
+        assert!(matches!(app.input_mode, InputMode::View));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn restore_and_purge_in_the_trash() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["first", "second"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.task_list.state.select(Some(0));
+        DeleteTaskCommand.execute(&mut app).unwrap();
+        app.task_list.state.select(Some(0));
+        DeleteTaskCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+
+        app.input_command = String::from("trash");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::Trash));
+        assert_eq!(app.task_list.items.len(), 2);
+        app.task_list.state.select(Some(0));
+        RestoreTaskCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+
+        app.task_list.state.select(Some(1));
+        let title = app.task_list.items[1].title.clone();
+        execute_or_confirm(PurgeTaskCommand, &mut app).unwrap();
+        assert_eq!(
+            app.confirmation.as_ref().unwrap().message,
+            format!("Permanently delete \"{title}\"? It can't be undone")
+        );
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        EmptyTrashCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+
+        ExitTrashCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::View));
+        assert!(app.task_list.items.is_empty());
+    }
+
+    #[test]
+    fn archive_and_hide_completed_tasks() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["first", "second"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.task_list.state.select(Some(0));
+        let err = ArchiveTaskCommand.execute(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "Only completed tasks can be archived");
+
+        ToggleTaskStatusCommand.execute(&mut app).unwrap();
+        ToggleCompletedCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        ToggleCompletedCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+
+        let completed = app.task_list.items.iter().position(|task| task.completed).unwrap();
+        app.task_list.state.select(Some(completed));
+        ArchiveTaskCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+        RedoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+
+        app.input_command = String::from("archive");
+        RunCommandLineCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::Archive));
+        assert_eq!(app.task_list.items.len(), 1);
+        app.task_list.state.select(Some(0));
+        UnarchiveTaskCommand.execute(&mut app).unwrap();
+        assert!(app.task_list.items.is_empty());
+        assert_eq!(app.info.as_deref(), Some("Took 1 tasks out of the archive"));
+    }
+
+    #[test]
+    fn archiving_a_task_with_open_subtasks_fails() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("parent");
+        AddTaskCommand.execute(&mut app).unwrap();
+        let parent = app.task_list.items[0].id;
+        let subtask = Task { title: "subtask".to_string(), parent_id: Some(parent), ..Task::new() };
+        app.tasks_service.add_new_task(&subtask).unwrap();
+        app.tasks_service.toggle_task_status(parent, true).unwrap();
+        app.refresh_task_list().unwrap();
+
+        app.task_list.state.select(Some(0));
+        let err = ArchiveTaskCommand.execute(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "Complete the subtasks before archiving the task");
+        assert_eq!(app.task_list.items.len(), 2);
+    }
+
+    #[test]
+    fn deleting_an_archived_task_asks_about_its_subtasks() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("parent");
+        AddTaskCommand.execute(&mut app).unwrap();
+        let parent = app.task_list.items[0].id;
+        let subtask = Task { title: "subtask".to_string(), parent_id: Some(parent), ..Task::new() };
+        let subtask = app.tasks_service.add_new_task(&subtask).unwrap();
+        app.tasks_service.toggle_task_status(subtask, true).unwrap();
+        app.tasks_service.toggle_task_status(parent, true).unwrap();
+        app.tasks_service.archive_task(parent).unwrap();
+
+        EnterArchiveCommand.execute(&mut app).unwrap();
+        let index = app.task_list.items.iter().position(|task| task.id == parent).unwrap();
+        app.task_list.state.select(Some(index));
+        execute_or_confirm(DeleteArchivedTaskCommand, &mut app).unwrap();
+        assert_eq!(app.confirmation.as_ref().unwrap().message, "Delete \"parent\" and its 1 subtasks?");
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert!(matches!(app.input_mode, InputMode::Archive));
+        assert!(app.task_list.items.is_empty());
+        assert_eq!(app.tasks_service.get_trash().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delete_a_count_of_tasks_with_one_confirmation() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        for title in ["first", "second", "third", "fourth"] {
+            app.input_title = TextInput::from(title);
+            AddTaskCommand.execute(&mut app).unwrap();
+        }
+        app.task_list.state.select(Some(1));
+        let kept = [app.task_list.items[0].id, app.task_list.items[3].id];
+        execute_or_confirm(DeleteTasksCommand { count: 2 }, &mut app).unwrap();
+        assert_eq!(
+            app.confirmation.as_ref().unwrap().message,
+            "Delete the 2 tasks from the selected one and their subtasks?"
+        );
+        assert_eq!(app.task_list.items.len(), 4);
+        ConfirmCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.iter().map(|t| t.id).collect::<Vec<_>>(), kept);
+
+        // a count past the end of the list stops at the last task, and one undo brings all back
+        app.task_list.state.select(Some(1));
+        DeleteTasksCommand { count: 5 }.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 1);
+        UndoCommand.execute(&mut app).unwrap();
+        assert_eq!(app.task_list.items.len(), 2);
+    }
+
+    #[test]
+    fn import_counts_new_and_updated_tasks_apart() {
+        let mut app = AppContext::new(String::new()).unwrap();
+        app.input_title = TextInput::from("exported");
+        AddTaskCommand.execute(&mut app).unwrap();
+        let path = std::env::temp_dir().join(format!("task_rustler_import_counts_{}.ics", std::process::id()));
+        app.input_export_path = TextInput::from(path.display().to_string());
+        app.input_mode = InputMode::Export;
+        FinishingExportCommand.execute(&mut app).unwrap();
+        let calendar = std::fs::read_to_string(&path).unwrap().replace(
+            "END:VCALENDAR",
+            "BEGIN:VTODO\r\nUID:new-task\r\nSUMMARY:new\r\nEND:VTODO\r\nEND:VCALENDAR",
+        );
+        std::fs::write(&path, calendar).unwrap();
+
+        app.input_import_path = TextInput::from(path.display().to_string());
+        FinishingImportCommand.execute(&mut app).unwrap();
+        assert_eq!(app.info.as_deref(), Some("Imported 1 new tasks, 1 updated"));
+        assert_eq!(app.task_list.items.len(), 2);
+        std::fs::remove_file(path).unwrap();
+    }
 }
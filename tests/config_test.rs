@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use task_rustler::config::{default_db_path, Config, Theme};
+    use task_rustler::task::Priority;
+    use task_rustler::task_manager::{SortBy, SortOrder};
+
+    #[test]
+    fn parse_every_setting() {
+        let config = Config::parse(
+            "database = \"/var/lib/tasks.db\"
+date_format = \"%Y-%m-%d\"
+timezone = \"Europe/Rome\"
+default_priority = \"high\"
+default_sort = \"date\"
+default_sort_order = \"low\"
+export_path = \"/tmp/calendars\"
+theme = \"light\"
+confirm = false
+archive_after_days = 14
+hide_completed = true",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                database: PathBuf::from("/var/lib/tasks.db"),
+                date_format: Some("%Y-%m-%d".to_string()),
+                timezone: Some("Europe/Rome".to_string()),
+                default_priority: Priority::High,
+                default_sort: SortBy::Date,
+                default_sort_order: SortOrder::Low,
+                export_path: Some(PathBuf::from("/tmp/calendars")),
+                theme: Theme::Light,
+                confirm: false,
+                archive_after_days: 14,
+                hide_completed: true,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn missing_settings_are_defaults() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.database, default_db_path());
+        assert!(config.database.ends_with("task-rustler/tasks.db"));
+        assert_eq!(config.default_priority, Priority::Low);
+        assert_eq!(config.theme, Theme::Dark);
+    }
+
+    #[test]
+    fn report_invalid_settings() {
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("default_priority = \"urgent\"").is_err());
+        assert!(Config::parse("theme = \"solarized\"").is_err());
+        let err = Config::parse("date_format = \"%H:%M\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid date_format"));
+        let err = Config::parse("timezone = \"Mars/Olympus\"").unwrap_err();
+        assert!(err.to_string().contains("Unknown timezone"));
+    }
+
+    #[test]
+    fn load_config_file() {
+        let path = std::env::temp_dir().join(format!("task_rustler_{}_config.toml", std::process::id()));
+        std::fs::write(&path, "default_priority = \"medium\"").unwrap();
+        assert_eq!(Config::load(Some(&path)).unwrap().default_priority, Priority::Medium);
+        std::fs::write(&path, "default_priority = ").unwrap();
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(format!("{err:#}").contains("Invalid config file"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(Config::load(Some(&path)).is_err());
+    }
+}
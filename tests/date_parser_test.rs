@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use task_rustler::date::{date_format_hint, is_valid_date_format, Due};
+    use task_rustler::date_parser::parse_date;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn parse_absolute_dates() {
+        let today = date(2026, 10, 18).unwrap();
+        assert_eq!(parse_date("20-10-2026", today), date(2026, 10, 20));
+        assert_eq!(parse_date("2026-10-20", today), date(2026, 10, 20));
+        assert_eq!(parse_date("31-02-2026", today), None);
+        assert_eq!(parse_date("", today), None);
+        assert_eq!(parse_date("someday", today), None);
+    }
+
+    #[test]
+    fn parse_relative_dates() {
+        // a Sunday
+        let today = date(2026, 10, 18).unwrap();
+        assert_eq!(parse_date("today", today), Some(today));
+        assert_eq!(parse_date(" Tomorrow ", today), date(2026, 10, 19));
+        assert_eq!(parse_date("yesterday", today), date(2026, 10, 17));
+        assert_eq!(parse_date("in 3 days", today), date(2026, 10, 21));
+        assert_eq!(parse_date("in 1 month", today), date(2026, 11, 18));
+        assert_eq!(parse_date("+2w", today), date(2026, 11, 1));
+        assert_eq!(parse_date("-1d", today), date(2026, 10, 17));
+        assert_eq!(parse_date("+1y", today), date(2027, 10, 18));
+        assert_eq!(parse_date("next week", today), date(2026, 10, 25));
+        assert_eq!(parse_date("eow", today), Some(today));
+        assert_eq!(parse_date("eom", today), date(2026, 10, 31));
+        assert_eq!(parse_date("eom", date(2026, 2, 10).unwrap()), date(2026, 2, 28));
+        assert_eq!(parse_date("eoy", today), date(2026, 12, 31));
+        assert_eq!(parse_date("in x days", today), None);
+        assert_eq!(parse_date("+2", today), None);
+    }
+
+    #[test]
+    fn parse_weekdays() {
+        // a Friday
+        let today = date(2026, 10, 16).unwrap();
+        assert_eq!(parse_date("mon", today), date(2026, 10, 19));
+        assert_eq!(parse_date("Friday", today), date(2026, 10, 23));
+        assert_eq!(parse_date("next fri", today), date(2026, 10, 23));
+        assert_eq!(parse_date("sat", today), date(2026, 10, 17));
+        assert_eq!(parse_date("next blursday", today), None);
+    }
+
+    #[test]
+    fn due_with_words_and_time() {
+        let due: Due = "tomorrow 09:30 Europe/Rome".parse().unwrap();
+        assert_eq!(due.time, chrono::NaiveTime::from_hms_opt(9, 30, 0));
+        assert_eq!(due.timezone, Some(chrono_tz::Tz::Europe__Rome));
+        assert_eq!("next fri".parse::<Due>().unwrap().time, None);
+        assert!("next fri 25:00".parse::<Due>().is_err());
+    }
+
+    #[test]
+    fn display_formats() {
+        assert!(is_valid_date_format("%Y-%m-%d"));
+        assert!(is_valid_date_format("%a %d %b"));
+        assert!(!is_valid_date_format("%d-%m-%Y %H:%M"));
+        assert!(!is_valid_date_format("%Q"));
+        assert!(!is_valid_date_format(" "));
+        assert_eq!(date_format_hint("%d-%m-%Y"), "dd-mm-yyyy");
+        assert_eq!(date_format_hint("%Y/%m/%d"), "yyyy/mm/dd");
+        assert_eq!(date_format_hint("%a %e %b %y"), "wday d mon yy");
+    }
+}
@@ -1,29 +1,31 @@
 #[cfg(test)]
 mod test {
-    use chrono::NaiveDate;
+    use chrono::{NaiveDate, TimeZone, Utc};
     use task_rustler::date::{TaskDate, DATE_FORMAT};
+    use task_rustler::project::DEFAULT_PROJECT_ID;
     use task_rustler::task::{Priority, Task};
-    use task_rustler::export::export_tasks_to_icalendar;
+    use task_rustler::export::{calendar_file_names, export_projects_to_icalendar, export_tasks_to_icalendar, import_tasks_from_icalendar};
+    use task_rustler::project::Project;
+    use task_rustler::recurrence::Recurrence;
+    use task_rustler::task_manager::TasksService;
     use icalendar::{Component, DatePerhapsTime, TodoStatus};
 
     #[test]
     fn tasks_to_icalendar(){
-        let task1 = Task{
-            id: 0,
+        let task1 = Task {
             title: "First task".to_string(),
             description: "Task n 1".to_string(),
-            completed: false,
-            priority: Priority::Low,
-            date: TaskDate(Some(NaiveDate::parse_from_str("15-10-2024", DATE_FORMAT).unwrap()))
+            date: TaskDate(Some(NaiveDate::parse_from_str("15-10-2024", DATE_FORMAT).unwrap())),
+            ..Task::new()
         };
 
-        let task2 = Task{
+        let task2 = Task {
             id: 1,
             title: "Second task".to_string(),
             description: "Task n 2".to_string(),
             completed: true,
             priority: Priority::High,
-            date: TaskDate(None)
+            ..Task::new()
         };
 
         let tasks = vec![task1, task2];
@@ -33,7 +35,7 @@ mod test {
         let todo1 = calendar.components[0].as_todo().unwrap();
         assert_eq!(todo1.get_summary().unwrap(), "First task");
         assert_eq!(todo1.get_description().unwrap(), "Task n 1");
-        assert_eq!(todo1.get_priority().unwrap(), 3);
+        assert_eq!(todo1.get_priority().unwrap(), 9);
         assert_eq!(todo1.get_end().unwrap(), DatePerhapsTime::Date(NaiveDate::parse_from_str("15-10-2024", DATE_FORMAT).unwrap()));
         assert_eq!(todo1.get_status().unwrap(), TodoStatus::NeedsAction);
 
@@ -44,4 +46,220 @@ mod test {
         assert_eq!(todo2.get_end(), None);
         assert_eq!(todo2.get_status().unwrap(), TodoStatus::Completed);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn one_calendar_per_project() {
+        let projects = vec![
+            Project { id: DEFAULT_PROJECT_ID, name: "Inbox".to_string() },
+            Project { id: 2, name: "Work".to_string() },
+            Project { id: 3, name: "Empty".to_string() },
+        ];
+        let tasks = vec![
+            Task { id: 1, title: "Inbox task".to_string(), ..Task::new() },
+            Task { id: 2, title: "Work task".to_string(), project_id: 2, ..Task::new() },
+            Task { id: 3, title: "Other work task".to_string(), project_id: 2, ..Task::new() },
+        ];
+        let calendars = export_projects_to_icalendar(&projects, &tasks);
+        assert_eq!(calendars.len(), 3);
+        assert_eq!(calendars[0].get_name().unwrap(), "Inbox");
+        assert_eq!(calendars[0].components.len(), 1);
+        assert_eq!(calendars[1].get_name().unwrap(), "Work");
+        assert_eq!(calendars[1].components.len(), 2);
+        assert_eq!(calendars[1].components[0].as_todo().unwrap().get_summary().unwrap(), "Work task");
+        assert!(calendars[2].components.is_empty());
+    }
+
+    #[test]
+    fn calendar_file_names_dont_clash() {
+        let projects = vec![
+            Project { id: DEFAULT_PROJECT_ID, name: "Inbox".to_string() },
+            Project { id: 2, name: "side projects".to_string() },
+            Project { id: 3, name: "side_projects".to_string() },
+            Project { id: 4, name: "INBOX".to_string() },
+            Project { id: 5, name: "side_projects-3".to_string() },
+        ];
+        assert_eq!(
+            calendar_file_names(&projects),
+            vec!["Inbox-1.ics", "side_projects-2.ics", "side_projects-3.ics", "INBOX-4.ics", "side_projects-3_.ics"]
+        );
+        assert_eq!(calendar_file_names(&projects[..1]), vec!["Inbox.ics"]);
+    }
+
+    #[test]
+    fn recurring_task_has_rrule() {
+        let task = Task {
+            title: "Weekly review".to_string(),
+            date: TaskDate(NaiveDate::from_ymd_opt(2024, 10, 18)),
+            recurrence: Some(Recurrence::Weekly { interval: 1, weekdays: vec![chrono::Weekday::Fri] }),
+            ..Task::new()
+        };
+        let after_completion = Task {
+            recurrence: Some(Recurrence::AfterCompletion { days: 3 }),
+            ..Task::new()
+        };
+        let calendar = export_tasks_to_icalendar("Task Rustler", &[task, after_completion]);
+        let todo = calendar.components[0].as_todo().unwrap();
+        assert_eq!(todo.property_value("RRULE").unwrap(), "FREQ=WEEKLY;INTERVAL=1;BYDAY=FR");
+        assert_eq!(todo.get_start().unwrap(), DatePerhapsTime::Date(NaiveDate::from_ymd_opt(2024, 10, 18).unwrap()));
+        let todo = calendar.components[1].as_todo().unwrap();
+        assert!(todo.property_value("RRULE").is_none());
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let tasks = vec![
+            Task {
+                title: "Weekly review".to_string(),
+                description: "Look back".to_string(),
+                priority: Priority::Medium,
+                date: TaskDate(NaiveDate::from_ymd_opt(2024, 10, 18)),
+                recurrence: Some(Recurrence::Weekly { interval: 1, weekdays: vec![chrono::Weekday::Fri] }),
+                uid: "1629758c-8147-4d69-8fec-927211919694".to_string(),
+                created_at: Utc.with_ymd_and_hms(2024, 10, 1, 8, 30, 0).single(),
+                updated_at: Utc.with_ymd_and_hms(2024, 10, 2, 9, 0, 0).single(),
+                ..Task::new()
+            },
+            Task {
+                title: "Done".to_string(),
+                completed: true,
+                priority: Priority::High,
+                uid: "ca6de31f-f599-4053-8197-19e34afb8591".to_string(),
+                ..Task::new()
+            },
+        ];
+        let calendar = export_tasks_to_icalendar("Task Rustler", &tasks).to_string();
+        let imported = import_tasks_from_icalendar(&calendar).unwrap();
+        assert!(imported.skipped.is_empty());
+        assert_eq!(imported.tasks, tasks);
+        assert!(calendar.contains("LAST-MODIFIED:20241002T090000Z"));
+        assert!(calendar.contains("DTSTAMP:20241002T090000Z"));
+    }
+
+    #[test]
+    fn importing_an_export_updates_instead_of_duplicating() {
+        let service = TasksService::default();
+        let id = service.add_new_task(&Task { title: "Pay rent".to_string(), ..Task::new() }).unwrap();
+        let stored = service.get_task(id).unwrap().unwrap();
+        assert!(!stored.uid.is_empty());
+        assert!(stored.created_at.is_some());
+
+        let calendar = export_tasks_to_icalendar("Task Rustler", &service.get_all_tasks().unwrap()).to_string();
+        assert!(calendar.contains(&format!("UID:{}", stored.uid)));
+        let mut imported = import_tasks_from_icalendar(&calendar).unwrap();
+        imported.tasks[0].title = "Pay the rent".to_string();
+        assert_eq!(service.import_tasks(&imported.tasks).unwrap(), vec![id]);
+        assert_eq!(service.length().unwrap(), 1);
+        let updated = service.get_task(id).unwrap().unwrap();
+        assert_eq!(updated.title, "Pay the rent");
+        assert_eq!(updated.uid, stored.uid);
+    }
+
+    #[test]
+    fn import_skips_other_components() {
+        let calendar = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:other tool\r
+BEGIN:VTODO\r
+UID:1\r
+SUMMARY:Call mum\r
+STATUS:NEEDS-ACTION\r
+PRIORITY:9\r
+DUE:20261020T090000Z\r
+END:VTODO\r
+BEGIN:VTODO\r
+UID:2\r
+DESCRIPTION:no summary\r
+END:VTODO\r
+BEGIN:VEVENT\r
+UID:3\r
+SUMMARY:Meeting\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let imported = import_tasks_from_icalendar(calendar).unwrap();
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(imported.tasks[0].title, "Call mum");
+        assert_eq!(imported.tasks[0].priority, Priority::Low);
+        assert_eq!(imported.tasks[0].date, TaskDate(NaiveDate::from_ymd_opt(2026, 10, 20)));
+        assert_eq!(imported.skipped, vec!["VTODO without a summary", "VEVENT \"Meeting\""]);
+        assert!(import_tasks_from_icalendar("not a calendar").is_err());
+    }
+
+    #[test]
+    fn import_priorities_by_rfc5545_ranges() {
+        let calendar: String = ["BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:other tool\r\n".to_string()]
+            .into_iter()
+            .chain((0..=9).map(|priority| format!("BEGIN:VTODO\r\nUID:{priority}\r\nSUMMARY:p{priority}\r\nPRIORITY:{priority}\r\nEND:VTODO\r\n")))
+            .chain(["BEGIN:VTODO\r\nUID:none\r\nSUMMARY:none\r\nEND:VTODO\r\nEND:VCALENDAR\r\n".to_string()])
+            .collect();
+        let priorities: Vec<Priority> = import_tasks_from_icalendar(&calendar)
+            .unwrap()
+            .tasks
+            .into_iter()
+            .map(|task| task.priority)
+            .collect();
+        let default = Task::new().priority;
+        assert_eq!(
+            priorities,
+            vec![
+                default.clone(),
+                Priority::High,
+                Priority::High,
+                Priority::High,
+                Priority::High,
+                Priority::Medium,
+                Priority::Low,
+                Priority::Low,
+                Priority::Low,
+                Priority::Low,
+                default,
+            ]
+        );
+    }
+
+    #[test]
+    fn exported_priorities_are_1_5_and_9() {
+        let tasks: Vec<Task> = [Priority::High, Priority::Medium, Priority::Low]
+            .into_iter()
+            .map(|priority| Task { title: "task".to_string(), priority, ..Task::new() })
+            .collect();
+        let calendar = export_tasks_to_icalendar("Task Rustler", &tasks);
+        let exported: Vec<u32> = calendar
+            .components
+            .iter()
+            .map(|component| component.as_todo().unwrap().get_priority().unwrap())
+            .collect();
+        assert_eq!(exported, vec![1, 5, 9]);
+        let imported = import_tasks_from_icalendar(&calendar.to_string()).unwrap();
+        assert_eq!(imported.tasks.into_iter().map(|task| task.priority).collect::<Vec<_>>(), vec![Priority::High, Priority::Medium, Priority::Low]);
+    }
+
+    #[test]
+    fn due_time_is_exported_with_its_tzid_and_vtimezone() {
+        let mut task = Task { title: "Call Rome".to_string(), uid: "7d7c5c1e-1111-4d69-8fec-927211919694".to_string(), ..Task::new() };
+        task.set_due(Some("20-10-2026 09:30 Europe/Rome".parse().unwrap()));
+        let calendar = export_tasks_to_icalendar("Task Rustler", &[task.clone()]).to_string();
+        assert!(calendar.contains("DUE;TZID=Europe/Rome:20261020T093000"));
+        assert!(calendar.contains("TZID:Europe/Rome\r\n"));
+        // summer time starts and ends on the last Sunday of March and October
+        assert!(calendar.contains("DTSTART:20260329T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\nTZNAME:CEST\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\n"));
+        assert!(calendar.contains("DTSTART:20261025T030000\r\nRRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\nTZNAME:CET\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\n"));
+        assert!(!calendar.contains("DTEND"));
+
+        let imported = import_tasks_from_icalendar(&calendar).unwrap();
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(imported.tasks[0].due(), task.due());
+        assert_eq!(imported.tasks[0].timezone, task.timezone);
+    }
+
+    #[test]
+    fn due_time_in_utc_has_no_timezone_property() {
+        let mut task = Task { title: "Standup".to_string(), ..Task::new() };
+        task.set_due(Some("20-10-2026 09:30 UTC".parse().unwrap()));
+        let calendar = export_tasks_to_icalendar("Task Rustler", &[task.clone()]).to_string();
+        assert!(calendar.contains("DUE:20261020T093000Z"));
+        assert!(!calendar.contains("TZID"));
+        let imported = import_tasks_from_icalendar(&calendar).unwrap();
+        assert_eq!(imported.tasks[0].due(), task.due());
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod test {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::collections::BTreeMap;
+    use task_rustler::config::Config;
+    use task_rustler::keymap::{Action, KeyChord, KeyMatch, KeyPreset, KeySequence, Keymap, Keys};
+
+    fn key(c: char) -> KeyChord {
+        KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyChord {
+        KeyChord::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn sequence(keys: &str) -> KeySequence {
+        keys.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_key_sequences() {
+        assert_eq!(sequence("ctrl+r"), KeySequence(vec![ctrl('r')]));
+        assert_eq!(sequence("Ctrl+Shift+a"), KeySequence(vec![ctrl('A')]));
+        assert_eq!(sequence("shift+tab"), KeySequence(vec![KeyChord::new(KeyCode::BackTab, KeyModifiers::NONE)]));
+        assert_eq!(sequence("A"), KeySequence(vec![key('A')]));
+        assert_eq!(sequence("+"), KeySequence(vec![key('+')]));
+        assert_eq!(sequence("space"), KeySequence(vec![key(' ')]));
+        assert_eq!(sequence("f5"), KeySequence(vec![KeyChord::new(KeyCode::F(5), KeyModifiers::NONE)]));
+        assert_eq!(sequence("gg"), KeySequence(vec![key('g'), key('g')]));
+        assert_eq!(sequence("g g"), sequence("gg"));
+        assert_eq!(sequence("z down"), KeySequence(vec![key('z'), KeyChord::new(KeyCode::Down, KeyModifiers::NONE)]));
+        assert!("".parse::<KeySequence>().is_err());
+        assert!("hyper+x".parse::<KeySequence>().is_err());
+        assert!("ctrl+nope".parse::<KeySequence>().is_err());
+    }
+
+    #[test]
+    fn shift_is_part_of_the_character() {
+        let event = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event), key('A'));
+        let event = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event), sequence("shift+tab").0[0]);
+    }
+
+    #[test]
+    fn display_key_sequences() {
+        assert_eq!(sequence("gg").to_string(), "gg");
+        assert_eq!(sequence("ctrl+r").to_string(), "Ctrl + r");
+        assert_eq!(sequence("space").to_string(), "Space");
+        assert_eq!(sequence("shift+tab").to_string(), "Shift + Tab");
+        assert_eq!(sequence("g down").to_string(), "g ↓");
+        assert_eq!(sequence("esc").to_string(), "Esc");
+    }
+
+    #[test]
+    fn default_bindings() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.lookup(&[key('a')]), KeyMatch::Action(Action::AddTask));
+        assert_eq!(keymap.lookup(&[key('A')]), KeyMatch::Action(Action::AddSubtask));
+        assert_eq!(keymap.lookup(&[ctrl('q')]), KeyMatch::Action(Action::Quit));
+        assert_eq!(keymap.lookup(&[key('g')]), KeyMatch::Pending);
+        assert_eq!(keymap.lookup(&[key('g'), key('g')]), KeyMatch::Action(Action::SelectFirst));
+        assert_eq!(keymap.lookup(&[key('g'), key('x')]), KeyMatch::Unbound);
+        assert_eq!(keymap.lookup(&[key('x')]), KeyMatch::Unbound);
+        for action in Action::ALL {
+            assert!(!keymap.keys(action).is_empty(), "{action:?} has no default keys");
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_default_keys() {
+        let overrides = BTreeMap::from([
+            (Action::SelectNext, Keys::Many(vec!["down".to_string(), "j".to_string()])),
+            (Action::AddTask, Keys::One("n".to_string())),
+            // takes `s` from sort by priority, which keeps no key
+            (Action::Search, Keys::One("s".to_string())),
+        ]);
+        let keymap = Keymap::new(KeyPreset::Default, &overrides).unwrap();
+        assert_eq!(keymap.lookup(&[key('j')]), KeyMatch::Action(Action::SelectNext));
+        assert_eq!(keymap.lookup(&[key('n')]), KeyMatch::Action(Action::AddTask));
+        assert_eq!(keymap.lookup(&[key('a')]), KeyMatch::Unbound);
+        assert_eq!(keymap.lookup(&[key('s')]), KeyMatch::Action(Action::Search));
+        assert_eq!(keymap.lookup(&[key('/')]), KeyMatch::Unbound);
+        assert!(keymap.keys(Action::SortByPriority).is_empty());
+        assert_eq!(keymap.keys(Action::SelectNext), vec![&sequence("down"), &sequence("j")]);
+    }
+
+    #[test]
+    fn clashing_overrides_are_an_error() {
+        let overrides = BTreeMap::from([
+            (Action::AddTask, Keys::One("x".to_string())),
+            (Action::DeleteTask, Keys::One("xx".to_string())),
+        ]);
+        let err = Keymap::new(KeyPreset::Default, &overrides).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid key binding: xx of delete_task clashes with x of add_task");
+    }
+
+    #[test]
+    fn keys_from_the_config_file() {
+        let config = Config::parse(
+            "[keys]
+select_next = [\"down\", \"j\"]
+select_previous = [\"up\", \"k\"]
+quit = \"q\"",
+        )
+        .unwrap();
+        assert_eq!(config.keymap.lookup(&[key('k')]), KeyMatch::Action(Action::SelectPrevious));
+        assert_eq!(config.keymap.lookup(&[key('q')]), KeyMatch::Action(Action::Quit));
+        assert_eq!(config.keymap.lookup(&[ctrl('q')]), KeyMatch::Unbound);
+        assert!(Config::parse("[keys]\nfly = \"f\"").is_err());
+        let err = Config::parse("[keys]\nquit = \"ctrl+\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid key binding"));
+    }
+
+    #[test]
+    fn vim_preset() {
+        let keymap = Keymap::new(KeyPreset::Vim, &BTreeMap::new()).unwrap();
+        assert_eq!(keymap.lookup(&[key('j')]), KeyMatch::Action(Action::SelectNext));
+        assert_eq!(keymap.lookup(&[key('k')]), KeyMatch::Action(Action::SelectPrevious));
+        assert_eq!(keymap.lookup(&[key('G')]), KeyMatch::Action(Action::SelectLast));
+        assert_eq!(keymap.lookup(&[ctrl('d')]), KeyMatch::Action(Action::HalfPageDown));
+        assert_eq!(keymap.lookup(&[ctrl('u')]), KeyMatch::Action(Action::HalfPageUp));
+        assert_eq!(keymap.lookup(&[key('d')]), KeyMatch::Pending);
+        assert_eq!(keymap.lookup(&[key('d'), key('d')]), KeyMatch::Action(Action::DeleteTask));
+        assert_eq!(keymap.lookup(&[key('o')]), KeyMatch::Action(Action::AddTask));
+        assert_eq!(keymap.lookup(&[key('x')]), KeyMatch::Action(Action::ToggleStatus));
+        assert_eq!(keymap.lookup(&[key(':')]), KeyMatch::Action(Action::CommandLine));
+        assert_eq!(keymap.lookup(&[key('g'), key('t')]), KeyMatch::Action(Action::NextProject));
+        assert_eq!(keymap.lookup(&[key('?')]), KeyMatch::Action(Action::ToggleHelp));
+        for action in Action::ALL {
+            assert!(!keymap.keys(action).is_empty(), "{action:?} has no vim keys");
+        }
+        let config = Config::parse("key_preset = \"vim\"\n[keys]\ndelete_task = \"D\"").unwrap();
+        assert_eq!(config.keymap.lookup(&[key('j')]), KeyMatch::Action(Action::SelectNext));
+        assert_eq!(config.keymap.lookup(&[key('D')]), KeyMatch::Action(Action::DeleteTask));
+        assert_eq!(config.keymap.lookup(&[key('d')]), KeyMatch::Unbound);
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod test {
+    use ratatui::style::Modifier;
+    use ratatui::text::Line;
+    use task_rustler::markdown::markdown_lines;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn render_blocks() {
+        let lines = markdown_lines("# Plan\n- milk\n  * eggs\n- [ ] call\n- [x] pay\n1. first\n> quoted\n```\nlet *a* = 1;\n```\n---");
+        let texts: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(
+            texts,
+            vec!["Plan", "• milk", "  • eggs", "☐ call", "☑ pay", "1. first", "▌ quoted", "  let *a* = 1;", "─".repeat(24).as_str()]
+        );
+        assert!(lines[0].spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert!(lines[4].spans[1].style.add_modifier.contains(Modifier::CROSSED_OUT));
+    }
+
+    #[test]
+    fn render_inline_styles() {
+        let lines = markdown_lines("run `cargo test`, **really** *now* see [docs](https://example.com)");
+        let line = &lines[0];
+        assert_eq!(text(line), "run cargo test, really now see docs (https://example.com)");
+        let span = |content: &str| line.spans.iter().find(|span| span.content == content).unwrap();
+        assert!(span("really").style.add_modifier.contains(Modifier::BOLD));
+        assert!(span("now").style.add_modifier.contains(Modifier::ITALIC));
+        assert!(span("docs").style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(span("cargo test").style.fg.is_some());
+    }
+
+    #[test]
+    fn keep_unmatched_markers() {
+        let lines = markdown_lines("snake_case_name, 2 * 3 * 4, a `tick and #hashtag");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(text(&lines[0]), "snake_case_name, 2 * 3 * 4, a `tick and #hashtag");
+        assert!(lines[0].spans.iter().all(|span| span.style.add_modifier.is_empty()));
+    }
+}
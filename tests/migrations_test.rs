@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use rusqlite::Connection;
+    use std::path::PathBuf;
+    use task_rustler::date::TaskDate;
+    use task_rustler::error::Error;
+    use task_rustler::migrations::{migrate, schema_version, SCHEMA_VERSION};
+    use task_rustler::task_manager::TasksService;
+
+    fn temp_db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("task_rustler_{}_{name}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn new_database_is_at_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn unversioned_database_keeps_its_tasks() {
+        let path = temp_db_path("unversioned");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE tasks (
+                    id INTEGER PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    completed BOOLEAN NOT NULL,
+                    priority INTEGER NOT NULL,
+                    end_date DATE
+                );
+                INSERT INTO tasks (title, description, completed, priority, end_date)
+                VALUES ('old task', 'created before migrations', 0, 2, '19-11-2024'),
+                    ('done task', 'completed before migrations', 1, 1, NULL);",
+            )
+            .unwrap();
+        }
+        let service = TasksService::new(path.display().to_string()).unwrap();
+        assert_eq!(service.length().unwrap(), 2);
+        let task = service.get_task(1).unwrap().unwrap();
+        assert_eq!(task.title, "old task");
+        assert_eq!(task.date, TaskDate(NaiveDate::from_ymd_opt(2024, 11, 19)));
+        assert_eq!(task.uid.len(), 36);
+        assert!(task.updated_at.is_some());
+        assert!(task.completed_at.is_none());
+        let done = service.get_task(2).unwrap().unwrap();
+        assert_eq!(done.completed_at, done.updated_at);
+        drop(service);
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        let end_date: String = conn.query_row("SELECT end_date FROM tasks WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(end_date, "2024-11-19");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn refuse_database_newer_than_supported() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        let res = migrate(&mut conn);
+        assert!(matches!(res, Err(Error::SchemaTooNew { .. })));
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION + 1);
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use task_rustler::query::{Comparison, Query, Term};
+    use task_rustler::task::Priority;
+
+    fn term(term: Term) -> Query {
+        Query::Term(term)
+    }
+
+    #[test]
+    fn parse_terms() {
+        assert_eq!(
+            "priority:high due<2026-11-01 status:open tag:infra \"foo bar\"".parse::<Query>().unwrap(),
+            Query::And(vec![
+                term(Term::Priority(Comparison::Equal, Priority::High)),
+                term(Term::Due(Comparison::Less, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap())),
+                term(Term::Completed(false)),
+                term(Term::Tag("infra".to_string())),
+                term(Term::Text("foo bar".to_string())),
+            ])
+        );
+        assert_eq!(
+            "due>=01-11-2026".parse::<Query>().unwrap(),
+            term(Term::Due(Comparison::GreaterOrEqual, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()))
+        );
+        assert_eq!(
+            "project:\"side projects\"".parse::<Query>().unwrap(),
+            term(Term::Project("side projects".to_string()))
+        );
+        assert_eq!("due:none".parse::<Query>().unwrap(), term(Term::HasDue(false)));
+        assert_eq!(
+            "priority>=medium".parse::<Query>().unwrap(),
+            term(Term::Priority(Comparison::GreaterOrEqual, Priority::Medium))
+        );
+        let in_a_week = task_rustler::date::today() + chrono::Days::new(7);
+        assert_eq!(
+            "due<=+1w".parse::<Query>().unwrap(),
+            term(Term::Due(Comparison::LessOrEqual, in_a_week))
+        );
+        assert_eq!("  ".parse::<Query>().unwrap(), Query::all());
+    }
+
+    #[test]
+    fn parse_operators() {
+        assert_eq!(
+            "#home or -status:done (repeat:yes or not milk)".parse::<Query>().unwrap(),
+            Query::Or(vec![
+                term(Term::Tag("home".to_string())),
+                Query::And(vec![
+                    Query::Not(Box::new(term(Term::Completed(true)))),
+                    Query::Or(vec![
+                        term(Term::Recurring(true)),
+                        Query::Not(Box::new(term(Term::Text("milk".to_string())))),
+                    ]),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn report_invalid_queries() {
+        for invalid in ["priority:urgent", "due<tomorow", "due<+7é", "colour:red", "(tag:a", "tag:a)", "\"foo", "or tag:a"] {
+            let err = invalid.parse::<Query>().unwrap_err();
+            assert!(err.to_string().starts_with("Invalid filter"), "{invalid}: {err}");
+        }
+    }
+}
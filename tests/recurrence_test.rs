@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, Weekday};
+    use task_rustler::recurrence::Recurrence;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse_user_rules() {
+        assert_eq!("daily".parse(), Ok(Recurrence::Daily { interval: 1 }));
+        assert_eq!("every 3 days".parse(), Ok(Recurrence::Daily { interval: 3 }));
+        assert_eq!(
+            "weekly mon, Fri".parse(),
+            Ok(Recurrence::Weekly { interval: 1, weekdays: vec![Weekday::Mon, Weekday::Fri] })
+        );
+        assert_eq!(
+            "every 2 weeks".parse(),
+            Ok(Recurrence::Weekly { interval: 2, weekdays: vec![] })
+        );
+        assert_eq!("monthly 15th".parse(), Ok(Recurrence::Monthly { interval: 1, day: 15 }));
+        assert_eq!("after 10 days".parse(), Ok(Recurrence::AfterCompletion { days: 10 }));
+        assert!("monthly 32".parse::<Recurrence>().is_err());
+        assert!("every 0 days".parse::<Recurrence>().is_err());
+        assert!("sometimes".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn display_and_rrule_round_trip() {
+        let rules = vec![
+            Recurrence::Daily { interval: 2 },
+            Recurrence::Weekly { interval: 1, weekdays: vec![Weekday::Tue, Weekday::Thu] },
+            Recurrence::Monthly { interval: 3, day: 31 },
+            Recurrence::AfterCompletion { days: 5 },
+        ];
+        for rule in rules {
+            assert_eq!(rule.to_string().parse(), Ok(rule.clone()));
+            assert_eq!(Recurrence::from_rrule(&rule.to_db_string()), Ok(rule.clone()));
+        }
+        assert_eq!(
+            Recurrence::Monthly { interval: 1, day: 15 }.rrule().unwrap(),
+            "FREQ=MONTHLY;INTERVAL=1;BYMONTHDAY=15"
+        );
+    }
+
+    #[test]
+    fn next_date() {
+        let completed_on = date(2024, 12, 1);
+        // 2024-10-16 is a wednesday
+        let due = Some(date(2024, 10, 16));
+        assert_eq!(Recurrence::Daily { interval: 2 }.next_date(due, completed_on), Some(date(2024, 10, 18)));
+        assert_eq!(Recurrence::Daily { interval: 1 }.next_date(None, completed_on), Some(date(2024, 12, 2)));
+        let weekly = Recurrence::Weekly { interval: 1, weekdays: vec![Weekday::Mon, Weekday::Fri] };
+        assert_eq!(weekly.next_date(due, completed_on), Some(date(2024, 10, 18)));
+        let biweekly = Recurrence::Weekly { interval: 2, weekdays: vec![Weekday::Mon] };
+        assert_eq!(biweekly.next_date(due, completed_on), Some(date(2024, 10, 28)));
+        let monthly = Recurrence::Monthly { interval: 1, day: 31 };
+        assert_eq!(monthly.next_date(due, completed_on), Some(date(2024, 10, 31)));
+        // months without the day are skipped, like RRULE does
+        assert_eq!(monthly.next_date(Some(date(2024, 10, 31)), completed_on), Some(date(2024, 12, 31)));
+        let yearly = Recurrence::Monthly { interval: 12, day: 30 };
+        assert_eq!(yearly.next_date(Some(date(2024, 2, 1)), completed_on), None);
+        let after = Recurrence::AfterCompletion { days: 3 };
+        assert_eq!(after.next_date(due, completed_on), Some(date(2024, 12, 4)));
+    }
+}
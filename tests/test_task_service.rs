@@ -1,153 +1,583 @@
 #[cfg(test)]
 mod test {
-    use task_rustler::date::TaskDate;
+    use chrono::{Duration, NaiveDate, Utc};
+    use rusqlite::Connection;
+    use task_rustler::date::{Due, TaskDate};
+    use task_rustler::error::Error;
+    use task_rustler::project::{Project, DEFAULT_PROJECT_ID};
+    use task_rustler::query::Query;
+    use task_rustler::recurrence::Recurrence;
+    use task_rustler::search::highlighted_parts;
     use task_rustler::task::{Priority, Task};
-    use task_rustler::task_manager::SortOrder;
+    use task_rustler::task_manager::{SortBy, SortOrder};
     use task_rustler::task_manager::TasksService;
+    use task_rustler::view::{View, DEFAULT_VIEW_ID};
+
+    /// The task with `id` added by `setup`
+    fn fixture(id: i32) -> Task {
+        let (title, description, priority, date) = match id {
+            1 => ("My first task title", "First task", Priority::Low, Some("19-11-1976")),
+            2 => ("My second task title", "Second task", Priority::Medium, None),
+            _ => ("My third task title", "Third task", Priority::High, Some("19-11-2024")),
+        };
+        Task {
+            id,
+            title: title.to_string(),
+            description: description.to_string(),
+            priority,
+            date: date.map(|date| TaskDate::try_from(date.to_string()).unwrap()).unwrap_or(TaskDate(None)),
+            ..Task::new()
+        }
+    }
+
+    /// `expected` with the uid and the timestamps assigned to `stored` when it was stored
+    fn as_stored(expected: Task, stored: &Task) -> Task {
+        Task {
+            uid: stored.uid.clone(),
+            created_at: stored.created_at,
+            updated_at: stored.updated_at,
+            ..expected
+        }
+    }
 
     fn setup() -> TasksService {
         let tasks = TasksService::default();
-        let tasks_to_add = vec![
-            Task {
-                id: 1,
-                title: "My first task title".to_string(),
-                description: "First task".to_string(),
-                completed: false,
-                priority: Priority::Low,
-                date: TaskDate::try_from("19-11-1976".to_string()).unwrap(),
-            },
-            Task {
-                id: 2,
-                title: "My second task title".to_string(),
-                description: "Second task".to_string(),
-                completed: false,
-                priority: Priority::Medium,
-                date: TaskDate(None),
-            },
-            Task {
-                id: 3,
-                title: "My third task title".to_string(),
-                description: "Third task".to_string(),
-                completed: false,
-                priority: Priority::High,
-                date: TaskDate::try_from("19-11-2024".to_string()).unwrap(),
-            },
-        ];
-        for t in tasks_to_add {
-            tasks.add_new_task(&t)
+        for id in 1..=3 {
+            tasks.add_new_task(&fixture(id)).unwrap();
         }
         tasks
     }
 
     fn teardown(t: &TasksService) {
-        t.delete_all_tasks();
+        t.delete_all_tasks().unwrap();
     }
 
     #[test]
     fn get_all_tasks() {
         let t = setup();
-        assert_eq!(t.length(), 3);
+        assert_eq!(t.length().unwrap(), 3);
         teardown(&t);
     }
     #[test]
     fn should_return_task_if_id_exists() {
         let t = setup();
-        t.add_new_task(&Task::default());
-        let task = t.get_task(4).unwrap();
+        t.add_new_task(&Task::default()).unwrap();
+        let task = t.get_task(4).unwrap().unwrap();
         assert_eq!(task.id, 4);
         assert_eq!(task.description, "Test task description");
-        assert_eq!(task.completed, false);
+        assert!(!task.completed);
         assert!(task.date.0.is_none());
     }
     #[test]
     fn should_return_none_if_task_is_not_found() {
         let t = setup();
-        let task = t.get_task(100);
-        assert_eq!(task.is_none(), true);
+        let task = t.get_task(100).unwrap();
+        assert!(task.is_none());
     }
     #[test]
     fn set_completed_should_return_1_if_task_exists_0_otherwise() {
         let t = setup();
-        let num_tasks_completed = t.toggle_task_status(1, true);
+        let num_tasks_completed = t.toggle_task_status(1, true).unwrap();
         assert_eq!(num_tasks_completed, 1);
-        let num_tasks_completed = t.toggle_task_status(100, true);
+        let num_tasks_completed = t.toggle_task_status(100, true).unwrap();
         assert_eq!(num_tasks_completed, 0);
     }
 
     #[test]
     fn delete_task_should_return_1_if_task_exists_0_otherwise() {
         let t = setup();
-        let num_task_removed = t.delete_task(2);
+        let num_task_removed = t.delete_task(2).unwrap();
         assert_eq!(num_task_removed, 1);
-        let num_task_removed = t.delete_task(100);
+        let num_task_removed = t.delete_task(100).unwrap();
         assert_eq!(num_task_removed, 0);
     }
 
     #[test]
     fn get_all_the_task_sorted_by_highest_priority() {
         let t = setup();
-        let tasks = t.get_all_tasks_sorted_by_priority(SortOrder::High);
-        assert_eq!(
-            tasks[0],
-            Task {
-                id: 3,
-                title: "My third task title".to_string(),
-                description: "Third task".to_string(),
-                completed: false,
-                priority: Priority::High,
-                date: TaskDate::try_from("19-11-2024".to_string()).unwrap(),
-            }
-        );
+        let tasks = t.find_tasks(&Query::all(), SortBy::Priority, SortOrder::High).unwrap();
+        assert_eq!(tasks[0], as_stored(fixture(3), &tasks[0]));
     }
 
     #[test]
     fn get_all_the_task_sorted_by_lowest_priority() {
         let t = setup();
-        let tasks = t.get_all_tasks_sorted_by_priority(SortOrder::Low);
-        assert_eq!(
-            tasks[0],
-            Task {
-                id: 1,
-                title: "My first task title".to_string(),
-                description: "First task".to_string(),
-                completed: false,
-                priority: Priority::Low,
-                date: TaskDate::try_from("19-11-1976".to_string()).unwrap(),
-            }
-        );
+        let tasks = t.find_tasks(&Query::all(), SortBy::Priority, SortOrder::Low).unwrap();
+        assert_eq!(tasks[0], as_stored(fixture(1), &tasks[0]));
     }
 
     #[test]
     fn get_all_tasks_sorted_by_newest() {
         let t = setup();
-        let tasks = t.get_all_tasks_sorted_by_date(SortOrder::High);
-        assert_eq!(
-            tasks[0],
-            Task {
-                id: 3,
-                title: "My third task title".to_string(),
-                description: "Third task".to_string(),
-                completed: false,
-                priority: Priority::High,
-                date: TaskDate::try_from("19-11-2024".to_string()).unwrap(),
-            }
-        );
+        let tasks = t.find_tasks(&Query::all(), SortBy::Date, SortOrder::High).unwrap();
+        assert_eq!(tasks[0], as_stored(fixture(3), &tasks[0]));
     }
 
     #[test]
     fn get_all_tasks_sorted_by_oldest() {
         let t = setup();
-        let tasks = t.get_all_tasks_sorted_by_date(SortOrder::Low);
-        assert_eq!(
-            tasks[0],
-            Task {
-                id: 2,
-                title: "My second task title".to_string(),
-                description: "Second task".to_string(),
-                completed: false,
-                priority: Priority::Medium,
-                date: TaskDate(None),
-            }
-        );
+        let tasks = t.find_tasks(&Query::all(), SortBy::Date, SortOrder::Low).unwrap();
+        assert_eq!(tasks[0], as_stored(fixture(1), &tasks[0]));
+    }
+
+    #[test]
+    fn tasks_without_a_date_sort_last() {
+        let t = setup();
+        t.add_new_task(&Task { date: TaskDate::try_from("01-02-2024".to_string()).unwrap(), ..Task::new() }).unwrap();
+        let ids = |sort: SortOrder| -> Vec<i32> {
+            t.find_tasks(&Query::all(), SortBy::Date, sort).unwrap().iter().map(|task| task.id).collect()
+        };
+        // 1: 19-11-1976, 2: no date, 3: 19-11-2024, 4: 01-02-2024 sorts before 3 as a date, not as text
+        assert_eq!(ids(SortOrder::Low), vec![1, 4, 3, 2]);
+        assert_eq!(ids(SortOrder::High), vec![3, 4, 1, 2]);
+        assert_eq!(t.find_tasks(&"due<01-06-2024".parse().unwrap(), SortBy::Date, SortOrder::Low).unwrap().len(), 2);
+        // tasks without a due date don't match due<, so they match its negation
+        let not_before: Vec<i32> = t
+            .find_tasks(&"-due<01-06-2024".parse().unwrap(), SortBy::Date, SortOrder::Low)
+            .unwrap()
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(not_before, vec![3, 2]);
+    }
+
+    #[test]
+    fn corrupted_rows_are_reported_as_errors() {
+        let path = std::env::temp_dir().join(format!("task_rustler_{}_corrupted.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let t = TasksService::new(path.display().to_string()).unwrap();
+        t.add_new_task(&Task::default()).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute("UPDATE tasks SET end_date = 'not a date'", []).unwrap();
+        assert!(matches!(t.get_all_tasks(), Err(Error::Database(_))));
+
+        conn.execute("UPDATE tasks SET end_date = NULL, priority = 7", []).unwrap();
+        let err = t.get_task(1).unwrap_err();
+        assert!(err.to_string().contains("Invalid priority 7"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn tags_are_stored_and_updated() {
+        let t = setup();
+        let task = Task {
+            tags: Task::parse_tags("#infra, Backend infra"),
+            ..Default::default()
+        };
+        let id = t.add_new_task(&task).unwrap();
+        let mut task = t.get_task(id).unwrap().unwrap();
+        assert_eq!(task.tags, vec!["backend", "infra"]);
+
+        task.tags = vec!["frontend".to_string()];
+        t.update_task(&task).unwrap();
+        assert_eq!(t.get_task(id).unwrap().unwrap().tags, vec!["frontend"]);
+        assert_eq!(t.get_all_tags().unwrap(), vec!["frontend"]);
+
+        t.delete_task(id).unwrap();
+        assert!(t.get_all_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn filter_tasks_by_tags() {
+        let t = setup();
+        let mut task = t.get_task(1).unwrap().unwrap();
+        task.tags = vec!["home".to_string(), "urgent".to_string()];
+        t.update_task(&task).unwrap();
+        let mut task = t.get_task(3).unwrap().unwrap();
+        task.tags = vec!["urgent".to_string()];
+        t.update_task(&task).unwrap();
+
+        let find = |query: &str| {
+            t.find_tasks(&query.parse().unwrap(), SortBy::Priority, SortOrder::High)
+                .unwrap()
+                .iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(find("tag:urgent"), vec![3, 1]);
+        assert_eq!(find("#home tag:Urgent"), vec![1]);
+        assert_eq!(find("tag:home or -tag:urgent"), vec![2, 1]);
+        assert!(find("tag:missing").is_empty());
+    }
+
+    #[test]
+    fn projects_crud() {
+        let t = setup();
+        let projects = t.get_all_projects().unwrap();
+        assert_eq!(projects, vec![Project { id: DEFAULT_PROJECT_ID, name: "Inbox".to_string() }]);
+
+        let work_id = t.add_new_project(&Project::new("Work")).unwrap();
+        t.update_project(&Project { id: work_id, name: "Office".to_string() }).unwrap();
+        assert_eq!(t.get_all_projects().unwrap()[1].name, "Office");
+        assert!(t.add_new_project(&Project::new("Office")).is_err());
+
+        assert!(matches!(t.delete_project(DEFAULT_PROJECT_ID), Err(Error::DefaultProjectDeletion)));
+        assert_eq!(t.delete_project(work_id).unwrap(), 1);
+        assert_eq!(t.get_all_projects().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn tasks_are_scoped_to_their_project() {
+        let t = setup();
+        let work_id = t.add_new_project(&Project::new("Work")).unwrap();
+        let task = Task {
+            title: "Work task".to_string(),
+            tags: vec!["office".to_string()],
+            project_id: work_id,
+            ..Task::new()
+        };
+        let task_id = t.add_new_task(&task).unwrap();
+
+        let inbox = t
+            .find_tasks(&Query::all().in_project(DEFAULT_PROJECT_ID), SortBy::Priority, SortOrder::High)
+            .unwrap();
+        assert_eq!(inbox.len(), 3);
+        let work = t
+            .find_tasks(&"project:work".parse().unwrap(), SortBy::Date, SortOrder::Low)
+            .unwrap();
+        assert_eq!(work.len(), 1);
+        assert_eq!(work[0].id, task_id);
+
+        // deleting a project moves its tasks to the trash, in the default project
+        t.delete_project(work_id).unwrap();
+        assert!(t.get_task(task_id).unwrap().is_none());
+        assert_eq!(t.length().unwrap(), 3);
+        let trash = t.get_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].project_id, DEFAULT_PROJECT_ID);
+        assert_eq!(trash[0].tags, vec!["office"]);
+        t.restore_from_trash(task_id).unwrap();
+        assert_eq!(t.get_task(task_id).unwrap().unwrap().project_id, DEFAULT_PROJECT_ID);
+    }
+
+    #[test]
+    fn views_crud() {
+        let t = setup();
+        let views = t.get_all_views().unwrap();
+        assert_eq!(views[0].id, DEFAULT_VIEW_ID);
+        assert_eq!(views[0].query, "");
+        assert!(views.iter().any(|v| v.name == "Overdue"));
+
+        let view = View::new("Infra", "tag:infra status:open", SortBy::Date, SortOrder::Low);
+        let view_id = t.add_new_view(&view).unwrap();
+        let mut saved = t.get_all_views().unwrap().pop().unwrap();
+        assert_eq!(saved, View { id: view_id, ..view });
+        // names are looked up ignoring case, so they must be unique ignoring case too
+        assert!(t.add_new_view(&View::new("INFRA", "", SortBy::Date, SortOrder::Low)).is_err());
+
+        saved.sort_order = SortOrder::High;
+        t.update_view(&saved).unwrap();
+        assert_eq!(t.get_all_views().unwrap().pop().unwrap().sort_order, SortOrder::High);
+        saved.query = "priority:urgent".to_string();
+        assert!(matches!(t.update_view(&saved), Err(Error::InvalidQuery(_))));
+
+        assert!(matches!(t.delete_view(DEFAULT_VIEW_ID), Err(Error::DefaultViewDeletion)));
+        assert_eq!(t.delete_view(view_id).unwrap(), 1);
+        assert_eq!(t.get_all_views().unwrap().len(), views.len());
+    }
+
+    fn add_subtask(t: &TasksService, parent_id: i32, title: &str) -> i32 {
+        let task = Task {
+            title: title.to_string(),
+            parent_id: Some(parent_id),
+            ..Task::new()
+        };
+        t.add_new_task(&task).unwrap()
+    }
+
+    #[test]
+    fn task_tree_nests_subtasks_and_counts_progress() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        let grandchild = add_subtask(&t, child, "grandchild");
+        add_subtask(&t, 1, "second child");
+        t.toggle_task_status(grandchild, true).unwrap();
+
+        let forest = t
+            .get_task_tree(DEFAULT_PROJECT_ID, &Query::all(), SortBy::Priority, SortOrder::High)
+            .unwrap();
+        assert_eq!(forest.iter().map(|n| n.task.id).collect::<Vec<_>>(), vec![3, 2, 1]);
+        let root = &forest[2];
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].task.id, child);
+        assert_eq!(root.children[0].children[0].task.id, grandchild);
+        assert_eq!(root.progress(), (1, 3));
+        assert_eq!(t.get_subtasks(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delete_parent_deletes_subtasks() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        add_subtask(&t, child, "grandchild");
+        assert_eq!(t.delete_task(1).unwrap(), 1);
+        assert_eq!(t.length().unwrap(), 2);
+    }
+
+    #[test]
+    fn delete_parent_keeping_subtasks_moves_them_up() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        let grandchild = add_subtask(&t, child, "grandchild");
+        assert_eq!(t.delete_task_keeping_subtasks(child).unwrap(), 1);
+        assert_eq!(t.get_task(grandchild).unwrap().unwrap().parent_id, Some(1));
+
+        assert_eq!(t.delete_task_keeping_subtasks(1).unwrap(), 1);
+        assert_eq!(t.get_task(grandchild).unwrap().unwrap().parent_id, None);
+    }
+
+    #[test]
+    fn deleted_tasks_go_to_the_trash() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        let grandchild = add_subtask(&t, child, "grandchild");
+        t.delete_task(grandchild).unwrap();
+        assert_eq!(t.delete_task(1).unwrap(), 1);
+        assert_eq!(t.delete_task(1).unwrap(), 0);
+        assert_eq!(t.length().unwrap(), 2);
+        assert!(t.get_task(child).unwrap().is_none());
+        assert!(t.get_all_tasks().unwrap().iter().all(|task| task.deleted_at.is_none()));
+        assert!(t.search("first").unwrap().is_empty());
+        let trash = t.get_trash().unwrap();
+        assert_eq!(trash.len(), 3);
+        assert!(trash.iter().all(|task| task.deleted_at.is_some()));
+
+        // the grandchild was deleted before its parent and stays in the trash
+        assert_eq!(t.restore_from_trash(1).unwrap(), 1);
+        assert_eq!(t.get_task(child).unwrap().unwrap().parent_id, Some(1));
+        assert!(t.get_task(grandchild).unwrap().is_none());
+        assert_eq!(t.restore_from_trash(1).unwrap(), 0);
+
+        t.delete_task(child).unwrap();
+        t.restore_from_trash(grandchild).unwrap();
+        assert_eq!(t.get_task(grandchild).unwrap().unwrap().parent_id, None);
+        assert_eq!(t.length().unwrap(), 4);
+    }
+
+    #[test]
+    fn importing_a_deleted_subtask_survives_emptying_the_trash() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        let exported = t.get_task(child).unwrap().unwrap();
+        t.delete_task(1).unwrap();
+        assert_eq!(t.import_tasks(&[exported]).unwrap(), vec![child]);
+        assert_eq!(t.get_task(child).unwrap().unwrap().parent_id, None);
+
+        assert_eq!(t.empty_trash().unwrap(), 1);
+        assert_eq!(t.get_task(child).unwrap().unwrap().title, "child");
+    }
+
+    #[test]
+    fn purge_the_trash() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        t.delete_task(1).unwrap();
+        t.delete_task(2).unwrap();
+        assert_eq!(t.purge_task(3).unwrap(), 0);
+        assert_eq!(t.purge_task(1).unwrap(), 1);
+        assert_eq!(t.get_trash().unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(t.restore_from_trash(child).unwrap(), 0);
+
+        let mut old = t.get_trash().unwrap().remove(0);
+        old.deleted_at = Some(Utc::now() - Duration::days(10));
+        t.restore_task(&old).unwrap();
+        t.delete_task(3).unwrap();
+        assert_eq!(t.purge_expired_trash(0).unwrap(), 0);
+        assert_eq!(t.purge_expired_trash(30).unwrap(), 0);
+        assert_eq!(t.purge_expired_trash(7).unwrap(), 1);
+        assert_eq!(t.empty_trash().unwrap(), 1);
+        assert!(t.get_trash().unwrap().is_empty());
+        assert!(t.is_empty().unwrap());
+    }
+
+    #[test]
+    fn completing_a_task_records_when() {
+        let t = setup();
+        t.toggle_task_status(1, true).unwrap();
+        let completed_at = t.get_task(1).unwrap().unwrap().completed_at.unwrap();
+        t.toggle_task_status(1, true).unwrap();
+        assert_eq!(t.get_task(1).unwrap().unwrap().completed_at, Some(completed_at));
+        t.toggle_task_status(1, false).unwrap();
+        assert!(t.get_task(1).unwrap().unwrap().completed_at.is_none());
+    }
+
+    #[test]
+    fn archive_completed_tasks() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        let grandchild = add_subtask(&t, child, "grandchild");
+        t.toggle_task_status(1, true).unwrap();
+        t.toggle_task_status(child, true).unwrap();
+        // the grandchild is still open
+        assert!(t.has_open_subtasks(1).unwrap());
+        assert_eq!(t.archive_task(1).unwrap(), 0);
+        t.toggle_task_status(grandchild, true).unwrap();
+        assert!(!t.has_open_subtasks(1).unwrap());
+        assert_eq!(t.archive_task(1).unwrap(), 1);
+        assert_eq!(t.archive_task(1).unwrap(), 0);
+        let listed = t.find_tasks(&Query::all(), SortBy::Priority, SortOrder::Low).unwrap();
+        assert_eq!(listed.iter().map(|task| task.id).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(t.search("first").unwrap().is_empty());
+        assert!(t.get_task(child).unwrap().unwrap().archived_at.is_some());
+        assert_eq!(t.get_archive().unwrap().len(), 3);
+
+        assert_eq!(t.unarchive_task(child).unwrap(), 1);
+        assert_eq!(t.get_task(child).unwrap().unwrap().parent_id, None);
+        assert_eq!(t.unarchive_task(1).unwrap(), 1);
+        assert!(t.get_archive().unwrap().is_empty());
+
+        // opening an archived task again takes it out of the archive
+        t.archive_task(1).unwrap();
+        t.toggle_task_status(1, false).unwrap();
+        assert!(t.get_task(1).unwrap().unwrap().archived_at.is_none());
+    }
+
+    #[test]
+    fn importing_an_archived_subtask_as_open_moves_it_to_the_top_level() {
+        let t = setup();
+        let child = add_subtask(&t, 1, "child");
+        t.toggle_task_status(child, true).unwrap();
+        t.toggle_task_status(1, true).unwrap();
+        t.archive_task(1).unwrap();
+        let mut exported = t.get_task(child).unwrap().unwrap();
+        exported.completed = false;
+        t.import_tasks(&[exported]).unwrap();
+
+        let imported = t.get_task(child).unwrap().unwrap();
+        assert!(imported.archived_at.is_none());
+        assert_eq!(imported.parent_id, None);
+        let listed = t.find_tasks(&Query::all(), SortBy::Priority, SortOrder::Low).unwrap();
+        assert!(listed.iter().any(|task| task.id == child));
+    }
+
+    #[test]
+    fn archive_tasks_completed_long_ago() {
+        let t = setup();
+        let child = add_subtask(&t, 2, "child");
+        let grandchild = add_subtask(&t, child, "grandchild");
+        for id in [1, 2, 3, child] {
+            t.toggle_task_status(id, true).unwrap();
+            let mut task = t.get_task(id).unwrap().unwrap();
+            task.completed_at = Some(Utc::now() - Duration::days(10));
+            t.restore_task(&task).unwrap();
+        }
+        t.toggle_task_status(3, false).unwrap();
+        assert_eq!(t.archive_completed(0).unwrap(), 0);
+        assert_eq!(t.archive_completed(30).unwrap(), 0);
+        // the second task and its completed child have an open grandchild
+        assert_eq!(t.archive_completed(7).unwrap(), 1);
+        assert_eq!(t.get_archive().unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), vec![1]);
+        assert!(t.get_task(child).unwrap().unwrap().archived_at.is_none());
+
+        // once it is completed the whole subtree is archived
+        t.toggle_task_status(grandchild, true).unwrap();
+        assert_eq!(t.archive_completed(7).unwrap(), 3);
+        assert!(t.get_task(grandchild).unwrap().unwrap().archived_at.is_some());
+    }
+
+    #[test]
+    fn completing_a_recurring_task_adds_the_next_occurrence() {
+        let t = setup();
+        let task = Task {
+            title: "water the plants".to_string(),
+            date: TaskDate::try_from("19-11-2024".to_string()).unwrap(),
+            tags: vec!["home".to_string()],
+            recurrence: Some(Recurrence::Daily { interval: 3 }),
+            ..Task::new()
+        };
+        let id = t.add_new_task(&task).unwrap();
+        assert_eq!(t.toggle_task_status(id, true).unwrap(), 1);
+
+        let completed = t.get_task(id).unwrap().unwrap();
+        assert!(completed.completed);
+        assert!(completed.recurrence.is_none());
+
+        let next = t.get_task(id + 1).unwrap().unwrap();
+        assert!(!next.completed);
+        assert_eq!(next.title, "water the plants");
+        assert_eq!(next.tags, vec!["home"]);
+        assert_eq!(next.date, TaskDate(NaiveDate::from_ymd_opt(2024, 11, 22)));
+        assert_eq!(next.recurrence, Some(Recurrence::Daily { interval: 3 }));
+
+        // toggling again doesn't create other occurrences
+        t.toggle_task_status(id, false).unwrap();
+        t.toggle_task_status(id, true).unwrap();
+        assert_eq!(t.length().unwrap(), 5);
+    }
+
+    #[test]
+    fn search_ranks_title_matches_first_and_follows_changes() {
+        let t = TasksService::default();
+        let in_description = t
+            .add_new_task(&Task { title: "call the bank".to_string(), description: "ask about the mortgage".to_string(), ..Task::new() })
+            .unwrap();
+        let in_title = t
+            .add_new_task(&Task { title: "mortgage papers".to_string(), description: "sign them".to_string(), ..Task::new() })
+            .unwrap();
+        t.add_new_task(&Task { title: "groceries".to_string(), ..Task::new() }).unwrap();
+
+        let hits = t.search("mortg").unwrap();
+        assert_eq!(hits.iter().map(|h| h.task.id).collect::<Vec<_>>(), vec![in_title, in_description]);
+        assert_eq!(hits[0].title, "\u{2}mortgage\u{3} papers");
+        assert_eq!(highlighted_parts(&hits[0].title), vec![("mortgage", true), (" papers", false)]);
+        assert!(hits[1].snippet.contains("\u{2}mortgage\u{3}"));
+        assert!(t.search("\"mortgage OR -").unwrap().is_empty());
+        assert!(t.search("   ").unwrap().is_empty());
+
+        let mut task = t.get_task(in_title).unwrap().unwrap();
+        task.title = "loan papers".to_string();
+        t.update_task(&task).unwrap();
+        t.delete_task(in_description).unwrap();
+        assert!(t.search("mortgage").unwrap().is_empty());
+        assert_eq!(t.search("loan papers").unwrap()[0].task.id, in_title);
+    }
+
+    #[test]
+    fn due_time_is_stored_with_its_timezone() {
+        let path = std::env::temp_dir().join(format!("task_rustler_{}_due_time.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let t = TasksService::new(path.display().to_string()).unwrap();
+        let mut task = Task { title: "call Rome".to_string(), ..Task::new() };
+        task.set_due(Some("20-10-2026 09:30 Europe/Rome".parse().unwrap()));
+        let id = t.add_new_task(&task).unwrap();
+
+        let stored = t.get_task(id).unwrap().unwrap();
+        assert_eq!(stored.due().unwrap().to_string(), "20-10-2026 09:30 Europe/Rome");
+        assert_eq!(stored.due_at().unwrap().to_rfc3339(), "2026-10-20T09:30:00+02:00");
+
+        let conn = Connection::open(&path).unwrap();
+        let (due_at, due_tz): (String, String) = conn
+            .query_row("SELECT due_at, due_tz FROM tasks WHERE id = ?1", [id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(due_at, "2026-10-20T07:30:00Z");
+        assert_eq!(due_tz, "Europe/Rome");
+
+        // a whole day has no instant
+        let mut task = t.get_task(id).unwrap().unwrap();
+        task.set_due(Some(Due { time: None, ..task.due().unwrap() }));
+        t.update_task(&task).unwrap();
+        let stored = t.get_task(id).unwrap().unwrap();
+        assert_eq!(stored.due_time, None);
+        assert_eq!(stored.timezone, None);
+        assert_eq!(stored.date, TaskDate(NaiveDate::from_ymd_opt(2026, 10, 20)));
+        let due_at: Option<String> = conn
+            .query_row("SELECT due_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(due_at, None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn parse_due() {
+        let due: Due = "20-10-2026 09:30 America/New_York".parse().unwrap();
+        assert_eq!(due.date, NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        assert_eq!(due.time, chrono::NaiveTime::from_hms_opt(9, 30, 0));
+        assert_eq!(due.timezone, Some(chrono_tz::Tz::America__New_York));
+        assert_eq!("20-10-2026 09:30".parse::<Due>().unwrap().to_string(), "20-10-2026 09:30");
+        assert_eq!("20-10-2026".parse::<Due>().unwrap().to_string(), "20-10-2026");
+        assert!("20-10-2026 9h".parse::<Due>().is_err());
+        assert!("20-10-2026 09:30 Mars/Olympus".parse::<Due>().unwrap_err().to_string().contains("Unknown timezone"));
+        assert!("20-10-2026 09:30 UTC extra".parse::<Due>().is_err());
     }
 }
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod test {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use task_rustler::text_input::TextInput;
+
+    fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        input.handle_key(KeyEvent::new(code, modifiers))
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        for c in text.chars() {
+            press(&mut input, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        input
+    }
+
+    #[test]
+    fn insert_and_delete_at_the_cursor() {
+        let mut input = typed("helo");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Char('l'), KeyModifiers::NONE);
+        assert_eq!(input.value(), "hello");
+        press(&mut input, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(input.value(), "ello");
+        press(&mut input, KeyCode::Char('H'), KeyModifiers::SHIFT);
+        assert_eq!(input.value(), "Hello");
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.value(), "Hell");
+        assert_eq!(input.cursor(), 4);
+        // the cursor stays at the ends
+        press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(input.value(), "Hell");
+        assert!(!press(&mut input, KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!press(&mut input, KeyCode::Tab, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn edit_by_grapheme() {
+        // e followed by a combining acute accent is one character on screen
+        let mut input = TextInput::from("cafe\u{301} 👍🏽");
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.value(), "cafe\u{301} ");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(input.value(), "caf ");
+    }
+
+    #[test]
+    fn jump_and_delete_words() {
+        let mut input = TextInput::from("buy milk, eggs");
+        press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(input.cursor(), "buy milk, ".len());
+        press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(input.cursor(), "buy ".len());
+        press(&mut input, KeyCode::Char('b'), KeyModifiers::ALT);
+        assert_eq!(input.cursor(), 0);
+        press(&mut input, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(input.cursor(), "buy".len());
+        press(&mut input, KeyCode::Delete, KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "buy, eggs");
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "buy, ");
+        press(&mut input, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(input.value(), "");
+    }
+
+    #[test]
+    fn delete_to_the_ends() {
+        let mut input = TextInput::from("one two three");
+        press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut input, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "one two ");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), " ");
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn paste_on_one_line() {
+        let mut input = TextInput::from("ab");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        input.insert_str("first\r\nsecond\tline\n");
+        assert_eq!(input.value(), "afirst secondlineb");
+        assert_eq!(input.cursor(), "afirst secondline".len());
+    }
+
+    #[test]
+    fn cursor_width_on_screen() {
+        let mut input = TextInput::from("日本 ok");
+        assert_eq!(input.cursor(), "日本 ok".len());
+        assert_eq!(input.cursor_width(), 7);
+        assert_eq!(input.scroll_offset(20), 0);
+        assert_eq!(input.scroll_offset(5), 3);
+        press(&mut input, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(input.cursor_width(), 2);
+        assert_eq!(input.take(), "日本 ok");
+        assert!(input.is_empty());
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn edit_several_lines() {
+        let mut input = TextInput::multiline();
+        for c in "first".chars() {
+            press(&mut input, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert!(press(&mut input, KeyCode::Enter, KeyModifiers::NONE));
+        input.insert_str("ab\r\nlonger line");
+        assert_eq!(input.value(), "first\nab\nlonger line");
+        assert_eq!(input.cursor_row(), 2);
+        assert_eq!(input.scroll_rows(2), 1);
+        // the column is kept when the line is long enough
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.cursor(), "first\nab".len());
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.cursor(), "fi".len());
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(input.cursor(), "first\nab\nlo".len());
+        press(&mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(input.value(), "first\nab\nnger line");
+        assert_eq!(input.cursor_width(), 0);
+    }
+}